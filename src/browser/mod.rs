@@ -0,0 +1,127 @@
+mod render;
+mod state;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::io;
+
+use crate::cleaning::delete_files_scheduled_for_deletion;
+use crate::logging::process_directory_tree::FileSystemStack;
+use crate::utils::format_size;
+use render::render;
+use state::BrowserState;
+
+pub enum BrowserMode {
+    Browsing,
+    ConfirmDelete,
+}
+
+/// The state of a browsing session for a single scanned folder.
+pub struct BrowserApp {
+    state: BrowserState,
+    mode: BrowserMode,
+    // Shown in the header, e.g. which folder of a config group is being browsed.
+    title: String,
+    // The outcome of the last action, shown in place of the key help.
+    status: Option<String>,
+}
+
+impl BrowserApp {
+    fn delete_marked(&mut self) {
+        let marked = self.state.marked_files();
+        let summary = delete_files_scheduled_for_deletion(&marked);
+
+        self.status = Some(match summary.is_complete() {
+            true => format!(
+                "Deleted {} files ({})",
+                summary.deleted.len(),
+                format_size(summary.deleted_size)
+            ),
+            false => format!(
+                "Deleted {} files, {} could not be deleted",
+                summary.deleted.len(),
+                summary.failed.len()
+            ),
+        });
+        self.state.remove_deleted(&summary.deleted);
+    }
+
+    // Returns false once the user asks to quit.
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        if let BrowserMode::ConfirmDelete = self.mode {
+            self.mode = BrowserMode::Browsing;
+            match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.delete_marked(),
+                _ => self.status = Some("Deletion cancelled".to_string()),
+            }
+            return true;
+        }
+
+        self.status = None;
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.state.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.state.move_cursor(1),
+            KeyCode::PageUp => self.state.move_cursor(-10),
+            KeyCode::PageDown => self.state.move_cursor(10),
+            KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => self.state.enter_directory(),
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.state.leave_directory(),
+            KeyCode::Char(' ') => {
+                self.state.toggle_mark();
+                self.state.move_cursor(1);
+            }
+            KeyCode::Char('c') => self.state.toggle_candidates_only(),
+            KeyCode::Char('d') => match self.state.marked.is_empty() {
+                true => self.status = Some("Nothing is marked".to_string()),
+                false => self.mode = BrowserMode::ConfirmDelete,
+            },
+            _ => {}
+        }
+        true
+    }
+}
+
+fn run_event_loop(terminal: &mut DefaultTerminal, app: &mut BrowserApp) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| render(frame, app))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let was_confirming = matches!(app.mode, BrowserMode::ConfirmDelete);
+            if !app.handle_key(key.code) {
+                return Ok(());
+            }
+            // Files that can't be deleted are reported on stderr, which leaves
+            // stray text on the screen, so redraw everything after deleting.
+            if was_confirming {
+                terminal.clear()?;
+            }
+        }
+    }
+}
+
+/// Opens a full-screen browser over a scanned folder, where entries can be
+/// explored by size, marked and deleted.
+///
+/// # Arguments
+///
+/// * `stack` - The scanned folder, including the files that would be kept.
+/// * `title` - Shown before the current directory in the header.
+pub fn browse_folder(stack: FileSystemStack, title: String) -> io::Result<()> {
+    let Some(state) = BrowserState::new(stack) else {
+        return Ok(());
+    };
+    let mut app = BrowserApp {
+        state,
+        mode: BrowserMode::Browsing,
+        title,
+        status: None,
+    };
+
+    let mut terminal = ratatui::try_init()?;
+    let result = run_event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,157 @@
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+use super::state::{BrowserState, MarkState};
+use super::{BrowserApp, BrowserMode};
+use crate::logging::nested_tree::TreeNode;
+use crate::logging::style::current_style;
+use crate::utils::{escape_path, format_size};
+
+const BAR_WIDTH: usize = 10;
+const HELP_TEXT: &str =
+    "↑↓ move  → open  ← back  space mark  c candidates only  d delete marked  q quit";
+
+// Colours are only used if the rest of the output would be coloured too.
+fn colour(style: Style, colour: Color) -> Style {
+    match current_style().uses_colour() {
+        true => style.fg(colour),
+        false => style,
+    }
+}
+
+// A bar showing the entry's share of the largest entry in the directory.
+fn size_bar(size: u64, largest: u64) -> String {
+    let filled = match largest {
+        0 => 0,
+        _ => (size as f64 / largest as f64 * BAR_WIDTH as f64).round() as usize,
+    };
+    format!("[{:<width$}]", "#".repeat(filled), width = BAR_WIDTH)
+}
+
+fn entry_item<'a>(state: &BrowserState, entry: &TreeNode, largest: u64) -> ListItem<'a> {
+    let mark = match state.mark_state(entry) {
+        MarkState::Marked => "[x]",
+        MarkState::Partial => "[~]",
+        MarkState::Unmarked => "[ ]",
+    };
+    let size = state.display_size(entry);
+    let name = match entry.is_dir {
+        true => format!("{}/", entry.name()),
+        false => entry.name(),
+    };
+    let line = format!(
+        "{} {:>12} {} {}",
+        mark,
+        format_size(size),
+        size_bar(size, largest),
+        name
+    );
+
+    let style = match (state.is_candidate(entry), entry.is_dir) {
+        (true, false) => colour(Style::default(), Color::Red),
+        (_, true) => colour(Style::default(), Color::Blue).add_modifier(Modifier::BOLD),
+        (false, false) => Style::default(),
+    };
+    ListItem::new(line).style(style)
+}
+
+fn render_header(frame: &mut Frame, app: &BrowserApp, area: Rect) {
+    let directory = app.state.current_directory();
+    let mode = match app.state.candidates_only {
+        true => " (deletion candidates only)",
+        false => "",
+    };
+    let header = format!(
+        "{} {} - {}{}",
+        app.title,
+        escape_path(&directory.leaf.key),
+        format_size(app.state.display_size(directory)),
+        mode
+    );
+    frame.render_widget(
+        Paragraph::new(header).style(Style::default().add_modifier(Modifier::BOLD)),
+        area,
+    );
+}
+
+fn render_entries(frame: &mut Frame, app: &BrowserApp, area: Rect) {
+    let entries = app.state.entries();
+    let largest = entries
+        .iter()
+        .map(|entry| app.state.display_size(entry))
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| entry_item(&app.state, entry, largest))
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default().with_selected(Some(app.state.cursor));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn render_footer(frame: &mut Frame, app: &BrowserApp, area: Rect) {
+    let marked = format!(
+        "{} marked ({})",
+        app.state.marked.len(),
+        format_size(app.state.marked_size())
+    );
+    let status = app.status.as_deref().unwrap_or(HELP_TEXT);
+    let footer = Line::from(format!("{}  |  {}", marked, status));
+    frame.render_widget(Paragraph::new(footer), area);
+}
+
+fn centred(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+fn render_confirmation(frame: &mut Frame, app: &BrowserApp) {
+    let question = format!(
+        "Delete {} marked files ({})? This can't be undone.\n\nPress y to delete, any other key to cancel.",
+        app.state.marked.len(),
+        format_size(app.state.marked_size())
+    );
+    let area = centred(frame.area(), 60, 6);
+    let block = Block::default()
+        .title(" Confirm deletion ")
+        .borders(Borders::ALL)
+        .border_style(colour(Style::default(), Color::Red));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(question)
+            .block(block)
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+/// Draws the browser: a header with the current directory, its entries and a footer.
+pub fn render(frame: &mut Frame, app: &BrowserApp) {
+    let [header, entries, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    render_header(frame, app, header);
+    render_entries(frame, app, entries);
+    render_footer(frame, app, footer);
+
+    if let BrowserMode::ConfirmDelete = app.mode {
+        render_confirmation(frame, app);
+    }
+}
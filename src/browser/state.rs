@@ -0,0 +1,370 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::logging::folder_tree_helpers::DirTreeLeaf;
+use crate::logging::nested_tree::{build_nested_tree, TreeNode};
+use crate::logging::process_directory_tree::FileSystemStack;
+
+/// How much of an entry's selection is marked for deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkState {
+    Unmarked,
+    Partial,
+    Marked,
+}
+
+// The number and combined size of the deletion candidates beneath an entry.
+#[derive(Debug, Clone, Copy, Default)]
+struct CandidateTotals {
+    count: usize,
+    size: u64,
+}
+
+/// Everything the browser shows: the scanned tree, where the user is within it
+/// and which files they've marked.
+pub struct BrowserState {
+    // The scanned entries still on disk, from which the tree is rebuilt after deleting.
+    stack: FileSystemStack,
+    root: TreeNode,
+    candidate_totals: HashMap<PathBuf, CandidateTotals>,
+    // The directories opened on the way down from the root, outermost first.
+    open_directories: Vec<PathBuf>,
+    // The cursor position in each parent directory, so going back up restores it.
+    cursor_history: Vec<usize>,
+    pub cursor: usize,
+    pub marked: HashSet<PathBuf>,
+    pub candidates_only: bool,
+}
+
+impl BrowserState {
+    /// Builds the browser state from a scanned stack. Returns `None` if the stack is empty.
+    pub fn new(stack: FileSystemStack) -> Option<BrowserState> {
+        let root = build_sorted_tree(&stack)?;
+        let mut candidate_totals = HashMap::new();
+        total_candidates(&root, &mut candidate_totals);
+
+        Some(BrowserState {
+            stack,
+            root,
+            candidate_totals,
+            open_directories: Vec::new(),
+            cursor_history: Vec::new(),
+            cursor: 0,
+            marked: HashSet::new(),
+            candidates_only: false,
+        })
+    }
+
+    pub fn current_directory(&self) -> &TreeNode {
+        let mut node = &self.root;
+        for key in &self.open_directories {
+            match node.children.iter().find(|child| &child.leaf.key == key) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        node
+    }
+
+    fn is_visible(&self, node: &TreeNode) -> bool {
+        !self.candidates_only || self.candidate_totals(node).count > 0
+    }
+
+    /// The entries of the current directory that are shown, largest first.
+    pub fn entries(&self) -> Vec<&TreeNode> {
+        self.current_directory()
+            .children
+            .iter()
+            .filter(|child| self.is_visible(child))
+            .collect()
+    }
+
+    pub fn selected_entry(&self) -> Option<&TreeNode> {
+        self.entries().get(self.cursor).copied()
+    }
+
+    fn candidate_totals(&self, node: &TreeNode) -> CandidateTotals {
+        self.candidate_totals
+            .get(&node.leaf.key)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The size shown for an entry: everything beneath it, or just the deletion
+    /// candidates when only candidates are shown.
+    pub fn display_size(&self, node: &TreeNode) -> u64 {
+        match self.candidates_only {
+            true => self.candidate_totals(node).size,
+            false => node.total_size,
+        }
+    }
+
+    pub fn is_candidate(&self, node: &TreeNode) -> bool {
+        self.candidate_totals(node).count > 0
+    }
+
+    pub fn move_cursor(&mut self, offset: isize) {
+        let last = self.entries().len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(offset).min(last);
+    }
+
+    /// Opens the directory under the cursor.
+    pub fn enter_directory(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if !entry.is_dir || !entry.children.iter().any(|child| self.is_visible(child)) {
+            return;
+        }
+
+        let key = entry.leaf.key.clone();
+        self.open_directories.push(key);
+        self.cursor_history.push(self.cursor);
+        self.cursor = 0;
+    }
+
+    /// Goes back up to the parent directory.
+    pub fn leave_directory(&mut self) {
+        if self.open_directories.pop().is_some() {
+            self.cursor = self.cursor_history.pop().unwrap_or(0);
+            self.move_cursor(0);
+        }
+    }
+
+    pub fn toggle_candidates_only(&mut self) {
+        self.candidates_only = !self.candidates_only;
+        // The open directories may not hold any candidates, so start again from the top.
+        self.open_directories.clear();
+        self.cursor_history.clear();
+        self.cursor = 0;
+    }
+
+    // The files beneath a node that can be marked in the current view.
+    fn markable_files<'a>(&self, node: &'a TreeNode, files: &mut Vec<&'a Path>) {
+        if !self.is_visible(node) {
+            return;
+        }
+        match node.is_dir {
+            true => node
+                .children
+                .iter()
+                .for_each(|child| self.markable_files(child, files)),
+            false => files.push(&node.leaf.key),
+        }
+    }
+
+    pub fn mark_state(&self, node: &TreeNode) -> MarkState {
+        let mut files = Vec::new();
+        self.markable_files(node, &mut files);
+
+        let marked = files
+            .iter()
+            .filter(|file| self.marked.contains(**file))
+            .count();
+        match marked {
+            0 => MarkState::Unmarked,
+            count if count == files.len() => MarkState::Marked,
+            _ => MarkState::Partial,
+        }
+    }
+
+    /// Marks the entry under the cursor, along with every file beneath it. If it's
+    /// already fully marked, it's unmarked instead.
+    pub fn toggle_mark(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let unmark = self.mark_state(entry) == MarkState::Marked;
+        let mut files = Vec::new();
+        self.markable_files(entry, &mut files);
+        let files: Vec<PathBuf> = files.into_iter().map(Path::to_path_buf).collect();
+
+        for file in files {
+            match unmark {
+                true => self.marked.remove(&file),
+                false => self.marked.insert(file),
+            };
+        }
+    }
+
+    /// The leaves of every marked file, ready to be handed to the deletion step.
+    /// Marking a file schedules it, even if the filters would have kept it.
+    pub fn marked_files(&self) -> FileSystemStack {
+        self.stack
+            .iter()
+            .filter(|leaf| self.marked.contains(&leaf.key))
+            .map(|leaf| DirTreeLeaf {
+                keep_reason: None,
+                ..leaf.clone()
+            })
+            .collect()
+    }
+
+    pub fn marked_size(&self) -> u64 {
+        self.stack
+            .iter()
+            .filter(|leaf| self.marked.contains(&leaf.key))
+            .map(|leaf| leaf.size)
+            .sum()
+    }
+
+    /// Drops deleted files from the tree and recalculates every total.
+    pub fn remove_deleted(&mut self, deleted: &[PathBuf]) {
+        let deleted: HashSet<&PathBuf> = deleted.iter().collect();
+        self.stack.retain(|leaf| !deleted.contains(&leaf.key));
+        self.marked.retain(|path| !deleted.contains(path));
+
+        if let Some(root) = build_sorted_tree(&self.stack) {
+            self.root = root;
+        }
+        self.candidate_totals.clear();
+        total_candidates(&self.root, &mut self.candidate_totals);
+        self.move_cursor(0);
+    }
+}
+
+fn is_deletion_candidate(leaf: &DirTreeLeaf) -> bool {
+    leaf.rule.is_some()
+}
+
+fn build_sorted_tree(stack: &FileSystemStack) -> Option<TreeNode> {
+    let mut root = build_nested_tree(stack)?;
+    sort_by_size(&mut root);
+    Some(root)
+}
+
+fn sort_by_size(node: &mut TreeNode) {
+    node.children.sort_by(|a, b| {
+        b.total_size
+            .cmp(&a.total_size)
+            .then_with(|| a.name().cmp(&b.name()))
+    });
+    node.children.iter_mut().for_each(sort_by_size);
+}
+
+fn total_candidates(
+    node: &TreeNode,
+    candidate_totals: &mut HashMap<PathBuf, CandidateTotals>,
+) -> CandidateTotals {
+    let totals = match node.is_dir {
+        true => node
+            .children
+            .iter()
+            .map(|child| total_candidates(child, candidate_totals))
+            .fold(CandidateTotals::default(), |sum, child| CandidateTotals {
+                count: sum.count + child.count,
+                size: sum.size + child.size,
+            }),
+        false if is_deletion_candidate(&node.leaf) => CandidateTotals {
+            count: 1,
+            size: node.leaf.size,
+        },
+        false => CandidateTotals::default(),
+    };
+
+    candidate_totals.insert(node.leaf.key.clone(), totals);
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::mark_for_deletion::{DeletionRule, KeepReason};
+
+    fn build_state() -> BrowserState {
+        let leaves = vec![
+            ("root", 0, true, None),
+            ("root/small.log", 1, false, Some(5)),
+            ("root/media", 1, true, None),
+            ("root/media/film.mp4", 2, false, Some(500)),
+            ("root/media/notes.txt", 2, false, None),
+            ("root/keep.rs", 1, false, None),
+        ];
+
+        let stack = leaves
+            .into_iter()
+            .map(|(key, depth, is_dir, candidate_size)| {
+                let (rule, keep_reason) = match (is_dir, candidate_size) {
+                    (true, _) => (None, None),
+                    (false, Some(_)) => (Some(DeletionRule::AnyExtension), None),
+                    (false, None) => (None, Some(KeepReason::KeptExtension("x".to_string()))),
+                };
+                DirTreeLeaf {
+                    key: PathBuf::from(key),
+                    depth,
+                    is_dir,
+                    size: candidate_size.unwrap_or(50),
+                    rule,
+                    keep_reason,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        BrowserState::new(stack).unwrap()
+    }
+
+    fn entry_names(state: &BrowserState) -> Vec<String> {
+        state.entries().iter().map(|entry| entry.name()).collect()
+    }
+
+    #[test]
+    fn test_entries_sorted_by_size_and_navigable() {
+        let mut state = build_state();
+        assert_eq!(entry_names(&state), vec!["media", "keep.rs", "small.log"]);
+        assert_eq!(state.display_size(state.entries()[0]), 550);
+
+        state.enter_directory();
+        assert_eq!(entry_names(&state), vec!["film.mp4", "notes.txt"]);
+
+        state.move_cursor(5);
+        assert_eq!(state.cursor, 1);
+        state.leave_directory();
+        assert_eq!(state.cursor, 0);
+        assert_eq!(state.current_directory().name(), "root");
+    }
+
+    #[test]
+    fn test_candidates_only_hides_kept_files() {
+        let mut state = build_state();
+        state.toggle_candidates_only();
+
+        assert_eq!(entry_names(&state), vec!["media", "small.log"]);
+        assert_eq!(state.display_size(state.entries()[0]), 500);
+    }
+
+    #[test]
+    fn test_marking_a_folder_marks_visible_files() {
+        let mut state = build_state();
+        state.toggle_candidates_only();
+        state.toggle_mark();
+
+        let media = state.entries()[0];
+        assert_eq!(state.mark_state(media), MarkState::Marked);
+        assert_eq!(state.marked_size(), 500);
+
+        // With kept files visible again, the folder is only partly marked.
+        state.toggle_candidates_only();
+        let media = state.entries()[0];
+        assert_eq!(state.mark_state(media), MarkState::Partial);
+
+        state.toggle_mark();
+        assert_eq!(state.marked_size(), 550);
+        state.toggle_mark();
+        assert!(state.marked.is_empty());
+    }
+
+    #[test]
+    fn test_remove_deleted_updates_totals() {
+        let mut state = build_state();
+        state.toggle_mark();
+        state.remove_deleted(&[PathBuf::from("root/media/film.mp4")]);
+
+        // Ties in size fall back to the name.
+        assert_eq!(entry_names(&state), vec!["keep.rs", "media", "small.log"]);
+        let media = state.entries()[1];
+        assert_eq!(state.display_size(media), 50);
+        assert!(!state.is_candidate(media));
+        assert_eq!(state.marked_size(), 50);
+    }
+}
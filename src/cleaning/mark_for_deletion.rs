@@ -1,40 +1,264 @@
 use crate::configs::config::PathConfig;
 use crate::utils::is_hidden_file;
-use std::path::PathBuf;
+use glob::Pattern;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-// TODO: Add hidden file checks to the config?
-pub fn should_delete_file(path: &PathBuf, config: &PathConfig) -> bool {
-    if is_hidden_file(path) {
-        return false; // Skip hidden files
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The rule in a `PathConfig` that caused a file to be scheduled for deletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeletionRule {
+    // The file's extension is listed in `extensions_to_delete`
+    MatchedExtension(String),
+    // No extensions were listed, so any file with an extension is deleted
+    AnyExtension,
+    // The file's name, or the name of a folder it's in, matches `names_to_delete`
+    MatchedName(String),
+}
+
+impl fmt::Display for DeletionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeletionRule::MatchedExtension(ext) => write!(f, "extension .{}", ext),
+            DeletionRule::AnyExtension => write!(f, "any extension"),
+            DeletionRule::MatchedName(pattern) => write!(f, "name {}", pattern),
+        }
+    }
+}
+
+/// Why a file was left alone rather than scheduled for deletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepReason {
+    Hidden,
+    // The file's extension is listed in `extensions_to_keep`
+    KeptExtension(String),
+    NoExtension,
+    // `extensions_to_delete` doesn't list the file's extension
+    NotListed(String),
+    // The file was modified more recently than `older_than_days` allows
+    TooNew(u64),
+}
+
+impl fmt::Display for KeepReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeepReason::Hidden => write!(f, "hidden file"),
+            KeepReason::KeptExtension(ext) => write!(f, "extension .{} is kept", ext),
+            KeepReason::NoExtension => write!(f, "no extension"),
+            KeepReason::NotListed(ext) => write!(f, "extension .{} not listed", ext),
+            KeepReason::TooNew(days) => write!(f, "modified in the last {} days", days),
+        }
+    }
+}
+
+/// Decides whether `path` should be deleted, returning either the rule that schedules
+/// it for deletion or the reason it's kept.
+///
+/// Only the path and the file's modification time are needed, so scanned files can
+/// be classified again without going back to the disk.
+pub fn classify_file(
+    path: &Path,
+    modified: Option<SystemTime>,
+    config: &PathConfig,
+) -> Result<DeletionRule, KeepReason> {
+    if is_hidden_file(path) && !config.delete_hidden {
+        return Err(KeepReason::Hidden); // Skip hidden files
     }
 
-    // Check if the path has an extension and if it matches
-    // any of the extensions in the config.
-    let file_ext = match path.extension().and_then(|ext| ext.to_str()) {
-        Some(ext) => ext,
-        None => return false,
+    let file_ext = path.extension().and_then(|ext| ext.to_str());
+    if let Some(ext) = file_ext.filter(|ext| is_extension_to_keep(ext, &config.extensions_to_keep))
+    {
+        return Err(KeepReason::KeptExtension(ext.to_string()));
+    }
+
+    let rule = match name_deletion_rule(path, config) {
+        Some(rule) => rule,
+        None => {
+            // Check if the path has an extension and if it matches
+            // any of the extensions in the config.
+            let file_ext = file_ext.ok_or(KeepReason::NoExtension)?;
+            extension_deletion_rule(file_ext, config)
+                .ok_or_else(|| KeepReason::NotListed(file_ext.to_string()))?
+        }
     };
 
-    if is_extension_to_keep(file_ext, &config.extensions_to_keep) {
-        false
-    } else {
-        is_extension_to_delete(file_ext, &config.extensions_to_delete)
+    match config.older_than_days {
+        Some(days) if is_newer_than(modified, days) => Err(KeepReason::TooNew(days)),
+        _ => Ok(rule),
+    }
+}
+
+// Files without a modification time are treated as old enough to delete, since
+// the age cutoff can't rule them out.
+fn is_newer_than(modified: Option<SystemTime>, days: u64) -> bool {
+    let cutoff = SystemTime::now().checked_sub(Duration::from_secs(days * SECONDS_PER_DAY));
+    match (modified, cutoff) {
+        (Some(modified), Some(cutoff)) => modified > cutoff,
+        _ => false,
     }
 }
 
+// Extensions match regardless of case, so `log` also catches `.LOG` files.
+fn is_same_extension(listed: &str, ext: &str) -> bool {
+    listed.to_lowercase() == ext.to_lowercase()
+}
+
 fn is_extension_to_keep(ext: &str, extensions_to_keep: &Option<Vec<String>>) -> bool {
     // True if file is set to be kept
     match extensions_to_keep {
-        Some(extensions) => extensions.iter().any(|e| e == ext),
+        Some(extensions) => extensions.iter().any(|e| is_same_extension(e, ext)),
         None => false,
     }
 }
 
-fn is_extension_to_delete(ext: &str, extensions_to_delete: &Option<Vec<String>>) -> bool {
-    match extensions_to_delete {
-        // Check if empty or if the extension is in the list -> True in either instance
-        Some(extensions) => extensions.is_empty() || extensions.iter().any(|e| e == ext),
-        // true if the user has not explicitly specified any extensions to delete
-        None => true,
+// Matches the file's name and the folders between it and the folder being cleaned, so
+// `__pycache__` catches everything inside a `__pycache__` folder.
+fn name_deletion_rule(path: &Path, config: &PathConfig) -> Option<DeletionRule> {
+    let patterns = config.names_to_delete.as_deref()?;
+    let relative = path.strip_prefix(&config.directory).unwrap_or(path);
+    let names: Vec<&str> = relative
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+
+    patterns
+        .iter()
+        .filter_map(|pattern| Pattern::new(pattern).ok().map(|glob| (pattern, glob)))
+        .find(|(_, glob)| names.iter().any(|name| glob.matches(name)))
+        .map(|(pattern, _)| DeletionRule::MatchedName(pattern.clone()))
+}
+
+fn is_name_to_delete(name: &str, config: &PathConfig) -> bool {
+    config
+        .names_to_delete
+        .iter()
+        .flatten()
+        .any(|pattern| Pattern::new(pattern).is_ok_and(|glob| glob.matches(name)))
+}
+
+/// The outermost folder between `path` and the folder being cleaned whose name is
+/// listed in `names_to_delete`, e.g. the `target` folder a build artefact sits in.
+pub fn matched_directory(path: &Path, config: &PathConfig) -> Option<PathBuf> {
+    let relative = path.strip_prefix(&config.directory).ok()?;
+    let mut directory = config.directory.clone();
+    for component in relative.parent()?.components() {
+        directory.push(component);
+        let name = component.as_os_str().to_str()?;
+        if is_name_to_delete(name, config) {
+            return Some(directory);
+        }
+    }
+    None
+}
+
+fn extension_deletion_rule(ext: &str, config: &PathConfig) -> Option<DeletionRule> {
+    match &config.extensions_to_delete {
+        // Check if empty or if the extension is in the list -> a rule in either instance
+        Some(extensions) if extensions.is_empty() => Some(DeletionRule::AnyExtension),
+        Some(extensions) => extensions
+            .iter()
+            .any(|e| is_same_extension(e, ext))
+            .then(|| DeletionRule::MatchedExtension(ext.to_string())),
+        // Names on their own only delete what they match
+        None if config.names_to_delete.is_some() => None,
+        // Delete if the user has not explicitly specified any extensions to delete
+        None => Some(DeletionRule::AnyExtension),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_file_reports_why_files_are_kept() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.extensions_to_delete = Some(vec!["log".to_string(), "tmp".to_string()]);
+        config.extensions_to_keep = Some(vec!["tmp".to_string()]);
+
+        let classify = |name: &str| classify_file(Path::new(name), None, &config);
+        assert_eq!(
+            classify("root/app.log"),
+            Ok(DeletionRule::MatchedExtension("log".to_string()))
+        );
+        assert_eq!(classify("root/.app.log"), Err(KeepReason::Hidden));
+        assert_eq!(classify("root/Makefile"), Err(KeepReason::NoExtension));
+        assert_eq!(
+            classify("root/scratch.tmp"),
+            Err(KeepReason::KeptExtension("tmp".to_string()))
+        );
+        assert_eq!(
+            classify("root/main.rs"),
+            Err(KeepReason::NotListed("rs".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_classify_file_ignores_the_case_of_extensions() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.extensions_to_delete = Some(vec!["log".to_string(), "JPG".to_string()]);
+        config.extensions_to_keep = Some(vec!["Tmp".to_string()]);
+
+        let classify = |name: &str| classify_file(Path::new(name), None, &config);
+        assert_eq!(
+            classify("root/APP.LOG"),
+            Ok(DeletionRule::MatchedExtension("LOG".to_string()))
+        );
+        assert_eq!(
+            classify("root/photo.jpg"),
+            Ok(DeletionRule::MatchedExtension("jpg".to_string()))
+        );
+        assert_eq!(
+            classify("root/scratch.TMP"),
+            Err(KeepReason::KeptExtension("TMP".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_classify_file_with_hidden_files_and_age_cutoff() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.delete_hidden = true;
+        config.older_than_days = Some(30);
+
+        let path = Path::new("root/.cache.log");
+        let week_ago = SystemTime::now() - Duration::from_secs(7 * SECONDS_PER_DAY);
+        let year_ago = SystemTime::now() - Duration::from_secs(365 * SECONDS_PER_DAY);
+
+        assert_eq!(
+            classify_file(path, Some(week_ago), &config),
+            Err(KeepReason::TooNew(30))
+        );
+        assert_eq!(
+            classify_file(path, Some(year_ago), &config),
+            Ok(DeletionRule::AnyExtension)
+        );
+    }
+
+    #[test]
+    fn test_classify_file_matches_names_and_the_folders_files_are_in() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.names_to_delete = Some(vec!["*~".to_string(), "__pycache__".to_string()]);
+        config.extensions_to_keep = Some(vec!["py".to_string()]);
+
+        let classify = |name: &str| classify_file(Path::new(name), None, &config);
+        assert_eq!(
+            classify("root/notes.txt~"),
+            Ok(DeletionRule::MatchedName("*~".to_string()))
+        );
+        assert_eq!(
+            classify("root/app/__pycache__/main.cpython-312.pyc"),
+            Ok(DeletionRule::MatchedName("__pycache__".to_string()))
+        );
+        assert_eq!(
+            classify("root/app/main.py~.py"),
+            Err(KeepReason::KeptExtension("py".to_string()))
+        );
+        // Without any extensions listed, only the names are deleted.
+        assert_eq!(
+            classify("root/app/main.rs"),
+            Err(KeepReason::NotListed("rs".to_string()))
+        );
     }
 }
@@ -1,15 +1,34 @@
-mod mark_for_deletion;
+pub mod mark_for_deletion;
 pub mod track_files_for_deletion;
 
-use std::fs::remove_file;
+use std::collections::BTreeSet;
+use std::fs::{remove_dir, remove_file};
 use std::io;
+use std::path::PathBuf;
+use walkdir::WalkDir;
 
 use crate::configs::config::PathConfig;
 use crate::logging::process_directory_tree::FileSystemStack;
-use crate::logging::TextOverviewType;
-use track_files_for_deletion::track_files_for_deletion;
+use mark_for_deletion::matched_directory;
+use track_files_for_deletion::{track_files_for_deletion, DeletionMetaData};
 
-/// Processes a given configuration to track files for deletion and generate a deletion overview.
+/// The outcome of a deletion run: which files were removed and which couldn't be.
+#[derive(Debug, Default)]
+pub struct DeletionSummary {
+    pub deleted: Vec<PathBuf>,
+    pub deleted_size: u64,
+    pub failed: Vec<(PathBuf, io::Error)>,
+    // Folders matched by `names_to_delete` that were left empty and removed
+    pub removed_dirs: Vec<PathBuf>,
+}
+
+impl DeletionSummary {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Scans the folder described by a given configuration and tracks the files for deletion.
 ///
 /// # Arguments
 ///
@@ -17,74 +36,107 @@ use track_files_for_deletion::track_files_for_deletion;
 ///
 /// # Returns
 ///
-/// * `Ok(String)` - A string representing the deletion overview if the operation is successful.
+/// * `Ok((FileSystemStack, DeletionMetaData))` - The files scheduled for deletion, alongside
+///   summary metadata for the folder, if the operation is successful.
 /// * `Err(std::io::Error)` - An error if tracking files for deletion fails.
 pub fn track_files_for_deletion_in_given_config(
     config: &PathConfig,
-    deletion_overview: &TextOverviewType,
-) -> Result<(String, FileSystemStack), std::io::Error> {
-    let tracked_files = track_files_for_deletion(config);
-
-    match tracked_files {
-        Ok((file_folder_queue, file_folder_metadata)) => {
-            let text_overview = deletion_overview.generate_text(config, file_folder_metadata);
-            return Ok((text_overview, file_folder_queue));
-        }
-        Err(e) => {
-            eprintln!("Error tracking files for deletion: {}", e);
-            return Err(e);
-        }
-    }
+) -> Result<(FileSystemStack, DeletionMetaData), std::io::Error> {
+    track_files_for_deletion(config).inspect_err(|e| {
+        eprintln!("Error tracking files for deletion: {}", e);
+    })
+}
+
+/// Picks out the files in a scanned `FileSystemStack` that are due to be deleted, leaving
+/// behind directories and any files that are only being tracked to show they're kept.
+pub fn scheduled_files(stack: &FileSystemStack) -> FileSystemStack {
+    stack
+        .iter()
+        .filter(|leaf| leaf.is_scheduled_file())
+        .cloned()
+        .collect()
 }
 
 /// Attempts to delete files specified in a `FileSystemStack`.
 ///
 /// Iterates over `DirectoryLeaf` entries in the `files_for_deletion` stack, attempting
-/// to delete each. Logs errors for files that cannot be deleted and continues with others.
+/// to delete each file that's scheduled for deletion. Directories and files that are
+/// only tracked to show they're kept are skipped. Logs errors for files that cannot
+/// be deleted and continues with others.
 ///
 /// # Arguments
 /// * `files_for_deletion` - Stack of files scheduled for deletion.
 ///
 /// # Returns
-/// Returns `Ok(())` if all files are deleted, or `Err(io::Error)` if any deletions fail.
+/// Returns a `DeletionSummary` listing the files that were deleted and those that failed.
 ///
 /// # Example
 /// ```
 /// let mut files = FileSystemStack::new();
 /// files.push(DirectoryLeaf { key: PathBuf::from("/path/to/file.txt") });
 ///
-/// match delete_files_scheduled_for_deletion(files) {
-///     Ok(()) => println!("All files deleted successfully."),
-///     Err(e) => eprintln!("Error: {}", e),
+/// let summary = delete_files_scheduled_for_deletion(&files);
+/// if !summary.is_complete() {
+///     eprintln!("Warning: not all files could be deleted");
 /// }
 /// ```
 pub fn delete_files_scheduled_for_deletion(
-    files_for_deletion: FileSystemStack,
-) -> Result<(), io::Error> {
-    let mut errors: bool = false;
+    files_for_deletion: &FileSystemStack,
+) -> DeletionSummary {
+    let mut summary = DeletionSummary::default();
 
-    for directory_leaf in files_for_deletion {
-        let path = directory_leaf.key;
+    for directory_leaf in files_for_deletion
+        .iter()
+        .filter(|leaf| leaf.is_scheduled_file())
+    {
+        let path = directory_leaf.key.clone();
         if path.is_file() {
-            if let Err(e) = remove_file(&path) {
-                eprintln!("Failed to delete file {:?}: {}", &path, e);
-                errors = true
+            match remove_file(&path) {
+                Ok(_) => {
+                    summary.deleted_size += directory_leaf.size;
+                    summary.deleted.push(path);
+                }
+                Err(e) => {
+                    eprintln!("Failed to delete file {:?}: {}", &path, e);
+                    summary.failed.push((path, e));
+                }
             }
         }
     }
 
-    match errors {
-        true => Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Warning: not all files could be deleted",
-        )),
-        false => Ok(()),
+    summary
+}
+
+/// Removes the folders matched by `names_to_delete` that the deletion left empty, such
+/// as a `target` or `node_modules` folder, along with the empty folders inside them.
+/// Folders that still hold kept files are left in place, and so are their parents.
+pub fn prune_matched_directories(config: &PathConfig, summary: &mut DeletionSummary) {
+    let matched: BTreeSet<PathBuf> = summary
+        .deleted
+        .iter()
+        .filter_map(|path| matched_directory(path, config))
+        .collect();
+
+    for directory in matched {
+        // Contents come first, so each folder is only removed once it's been emptied
+        for entry in WalkDir::new(&directory)
+            .contents_first(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_dir())
+        {
+            if remove_dir(entry.path()).is_ok() {
+                summary.removed_dirs.push(entry.into_path());
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cleaning::mark_for_deletion::KeepReason;
+    use crate::cleaning::track_files_for_deletion::reclassify_tracked_files;
     use crate::logging::folder_tree_helpers::DirTreeLeaf;
     use std::collections::VecDeque;
     use std::fs::{self, File};
@@ -130,7 +182,7 @@ mod tests {
             all_files.push_back(DirTreeLeaf {
                 key: path,
                 depth: 1,
-                is_last: false,
+                ..Default::default()
             });
         }
         let sub_dir = base_dir.join("subdir");
@@ -139,13 +191,142 @@ mod tests {
             all_files.push_back(DirTreeLeaf {
                 key: path,
                 depth: 2,
-                is_last: false,
+                ..Default::default()
             });
         }
         assert_eq!(check_number_of_files(base_dir.to_path_buf()), 20);
 
-        let deletion_result = delete_files_scheduled_for_deletion(all_files);
-        assert!(deletion_result.is_ok());
+        let deletion_result = delete_files_scheduled_for_deletion(&all_files);
+        assert!(deletion_result.is_complete());
+        assert_eq!(deletion_result.deleted.len(), 20);
         assert_eq!(check_number_of_files(base_dir.to_path_buf()), 0);
     }
+
+    #[test]
+    fn test_delete_files_skips_kept_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path();
+        let mut stack = VecDeque::new();
+        stack.push_back(DirTreeLeaf::new_root(base_dir.to_path_buf()));
+        for path in create_files(base_dir, "txt", 2) {
+            stack.push_back(DirTreeLeaf {
+                key: path,
+                depth: 1,
+                ..Default::default()
+            });
+        }
+        stack[2].keep_reason = Some(KeepReason::KeptExtension("txt".to_string()));
+
+        let deletion_result = delete_files_scheduled_for_deletion(&stack);
+        assert_eq!(deletion_result.deleted, vec![base_dir.join("file1.txt")]);
+        assert!(base_dir.join("file2.txt").exists());
+    }
+
+    #[test]
+    fn test_tracking_rolls_up_directory_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path();
+        let deeper_dir = base_dir.join("sub").join("deeper");
+        fs::create_dir_all(&deeper_dir).expect("Failed to create subdirectories");
+        create_files(base_dir, "txt", 1);
+        create_files(&base_dir.join("sub"), "txt", 2);
+        create_files(&deeper_dir, "log", 1);
+
+        let mut config = PathConfig::new(base_dir.to_path_buf(), true);
+        config.extensions_to_delete = Some(vec!["txt".to_string()]);
+        config.recursive = true;
+        let (stack, _) = track_files_for_deletion_in_given_config(&config).unwrap();
+
+        let file_size = fs::metadata(base_dir.join("file1.txt")).unwrap().len();
+        let root = &stack[0];
+        assert_eq!(root.size, file_size * 4);
+        assert_eq!(root.totals.file_count, 4);
+        assert_eq!(root.totals.dir_count, 2);
+        assert_eq!(root.totals.deletion_count, 3);
+        assert_eq!(root.totals.deletion_size, file_size * 3);
+
+        let sub = stack
+            .iter()
+            .find(|leaf| leaf.key == base_dir.join("sub"))
+            .expect("The sub directory holds candidates");
+        assert_eq!(sub.size, file_size * 3);
+        assert_eq!(sub.totals.file_count, 3);
+        assert_eq!(sub.totals.deletion_count, 2);
+    }
+
+    #[test]
+    fn test_subfolders_are_only_scanned_when_recursive() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path();
+        let sub_dir = base_dir.join("sub");
+        fs::create_dir(&sub_dir).expect("Failed to create subdirectory");
+        create_files(base_dir, "txt", 1);
+        create_files(&sub_dir, "txt", 2);
+
+        let mut config = PathConfig::new(base_dir.to_path_buf(), true);
+        let (stack, metadata) = track_files_for_deletion_in_given_config(&config).unwrap();
+        assert_eq!(scheduled_files(&stack).len(), 1);
+        assert_eq!(metadata.file_count, 1);
+
+        config.recursive = true;
+        let (stack, metadata) = track_files_for_deletion_in_given_config(&config).unwrap();
+        assert_eq!(scheduled_files(&stack).len(), 3);
+        assert_eq!(metadata.file_count, 3);
+    }
+
+    #[test]
+    fn test_reclassifying_matches_a_fresh_scan() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path();
+        let sub_dir = base_dir.join("sub");
+        fs::create_dir(&sub_dir).expect("Failed to create subdirectory");
+        create_files(base_dir, "txt", 2);
+        create_files(&sub_dir, "log", 3);
+
+        let mut config = PathConfig::new(base_dir.to_path_buf(), true);
+        config.extensions_to_delete = Some(vec!["txt".to_string()]);
+        config.recursive = true;
+        config.track_kept_files = true;
+        let (mut stack, mut metadata) = track_files_for_deletion_in_given_config(&config).unwrap();
+        assert_eq!(metadata.file_count, 2);
+
+        config.extensions_to_delete = Some(vec!["log".to_string()]);
+        reclassify_tracked_files(&mut stack, &mut metadata, &config);
+        let (fresh_stack, fresh_metadata) =
+            track_files_for_deletion_in_given_config(&config).unwrap();
+
+        assert_eq!(metadata.file_count, 3);
+        assert_eq!(metadata.deletion_size, fresh_metadata.deletion_size);
+        assert_eq!(metadata.dir_count, fresh_metadata.dir_count);
+        assert_eq!(scheduled_files(&stack).len(), 3);
+        assert_eq!(stack[0].totals, fresh_stack[0].totals);
+    }
+
+    #[test]
+    fn test_matched_folders_are_pruned_once_emptied() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path();
+        let deps_dir = base_dir.join("target").join("debug").join("deps");
+        let kept_dir = base_dir.join("node_modules").join("kept");
+        fs::create_dir_all(&deps_dir).expect("Failed to create subdirectories");
+        fs::create_dir_all(&kept_dir).expect("Failed to create subdirectories");
+        create_files(&deps_dir, "rlib", 2);
+        create_files(&kept_dir, "js", 1);
+        create_files(base_dir, "txt", 1);
+
+        let mut config = PathConfig::new(base_dir.to_path_buf(), true);
+        config.names_to_delete = Some(vec!["target".to_string(), "node_modules".to_string()]);
+        config.extensions_to_keep = Some(vec!["js".to_string()]);
+        config.recursive = true;
+        let (stack, _) = track_files_for_deletion_in_given_config(&config).unwrap();
+
+        let mut summary = delete_files_scheduled_for_deletion(&stack);
+        prune_matched_directories(&config, &mut summary);
+
+        assert_eq!(summary.deleted.len(), 2);
+        assert_eq!(summary.removed_dirs.len(), 3);
+        assert!(!base_dir.join("target").exists());
+        assert!(kept_dir.join("file1.js").exists());
+        assert!(base_dir.join("file1.txt").exists());
+    }
 }
@@ -1,26 +1,78 @@
-use super::mark_for_deletion::should_delete_file;
+use super::mark_for_deletion::{classify_file, DeletionRule, KeepReason};
 use crate::configs::config::PathConfig;
-use crate::logging::folder_tree_helpers::DirTreeLeaf;
+use crate::logging::folder_tree_helpers::{DirTotals, DirTreeLeaf};
 use crate::utils::check_root_folder_exists;
-use std::collections::VecDeque;
-use std::fs::{self, metadata};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{self, metadata, Metadata};
 use std::io::Result as IoResult;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-type PathVec = Vec<PathBuf>;
+type EntryVec = Vec<ScannedEntry>;
 type TreeQueue = VecDeque<DirTreeLeaf>;
+// The total size and counts for each scanned directory, keyed by path.
+type DirectoryTotalsMap = HashMap<PathBuf, (u64, DirTotals)>;
 
+// A single entry found while scanning a folder, alongside the
+// metadata we want to carry through to the tree leaves.
+struct ScannedEntry {
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+    rule: Option<DeletionRule>,
+    keep_reason: Option<KeepReason>,
+}
+
+impl ScannedEntry {
+    fn from_dir(path: PathBuf) -> ScannedEntry {
+        ScannedEntry {
+            path,
+            is_dir: true,
+            size: 0,
+            modified: None,
+            rule: None,
+            keep_reason: None,
+        }
+    }
+
+    fn from_file(
+        path: PathBuf,
+        file_metadata: &Metadata,
+        verdict: Result<DeletionRule, KeepReason>,
+    ) -> ScannedEntry {
+        ScannedEntry {
+            path,
+            is_dir: false,
+            size: file_metadata.len(),
+            modified: file_metadata.modified().ok(),
+            rule: verdict.clone().ok(),
+            keep_reason: verdict.err(),
+        }
+    }
+}
+
+/// The number and combined size of the files sharing an extension.
+#[derive(Debug, Default, Clone)]
+pub struct ExtensionStats {
+    pub file_count: usize,
+    pub size: u64,
+}
+
+#[derive(Clone)]
 pub struct DeletionMetaData {
     pub folder_size: u64,
     pub deletion_size: u64,
     pub file_count: usize,
     pub dir_count: usize,
     pub last_modified_time: SystemTime,
+    // Every file in the folder, keyed by lowercase extension ("" for none).
+    pub extension_stats: BTreeMap<String, ExtensionStats>,
 }
 
 impl DeletionMetaData {
-    pub fn from_root_folder(folder_path: &PathBuf) -> DeletionMetaData {
+    pub fn from_root_folder(folder_path: &Path) -> DeletionMetaData {
         let last_modified_time = match metadata(folder_path) {
             Ok(metadata) => metadata.modified().unwrap_or_else(|_| SystemTime::now()),
             Err(_) => SystemTime::now(),
@@ -32,8 +84,19 @@ impl DeletionMetaData {
             file_count: 0,
             dir_count: 0,
             last_modified_time,
+            extension_stats: BTreeMap::new(),
         }
     }
+
+    fn record_extension(&mut self, path: &Path, size: u64) {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let stats = self.extension_stats.entry(extension).or_default();
+        stats.file_count += 1;
+        stats.size += size;
+    }
 }
 
 pub fn track_files_for_deletion(config: &PathConfig) -> IoResult<(TreeQueue, DeletionMetaData)> {
@@ -49,6 +112,7 @@ fn find_deletion_targets(config: &PathConfig) -> IoResult<(TreeQueue, DeletionMe
     // TODO: Check whether we can write this using a single VecDeque/Vec
     let mut queue: TreeQueue = VecDeque::new();
     let mut processed_leaves: TreeQueue = VecDeque::new();
+    let mut directory_totals: DirectoryTotalsMap = HashMap::new();
 
     // Add the root folder to the queue
     let root_leaf = DirTreeLeaf::new_root(config.directory.clone());
@@ -59,17 +123,28 @@ fn find_deletion_targets(config: &PathConfig) -> IoResult<(TreeQueue, DeletionMe
             processed_leaves.push_back(leaf);
             continue;
         }
+        // Without `recursive`, only the files directly inside the folder are looked at.
+        if leaf.depth > 0 && !config.recursive {
+            continue;
+        }
 
-        match scan_folder_contents(&leaf.key, config, &mut deletion_metadata) {
+        let folder_totals = directory_totals.entry(leaf.key.clone()).or_default();
+        match scan_folder_contents(&leaf.key, config, &mut deletion_metadata, folder_totals) {
             Ok(directory_contents) => {
                 let folder_leaves =
                     create_tree_leaves_from_paths(directory_contents, leaf.depth + 1);
-                if folder_leaves.len() == 0 {
+                if folder_leaves.is_empty() {
                     continue;
                 }
 
+                // Folders holding nothing but kept files don't count towards the total.
+                if folder_leaves
+                    .iter()
+                    .any(|leaf| leaf.is_dir || leaf.is_scheduled_file())
+                {
+                    deletion_metadata.dir_count += 1;
+                }
                 processed_leaves.push_back(leaf);
-                deletion_metadata.dir_count += 1;
 
                 for elem in folder_leaves.into_iter().rev() {
                     queue.push_front(elem);
@@ -79,61 +154,172 @@ fn find_deletion_targets(config: &PathConfig) -> IoResult<(TreeQueue, DeletionMe
         }
     }
 
+    roll_up_directory_totals(&config.directory, &mut directory_totals);
+    for leaf in processed_leaves.iter_mut().filter(|leaf| leaf.is_dir) {
+        if let Some((size, totals)) = directory_totals.get(&leaf.key) {
+            leaf.size = *size;
+            leaf.totals = *totals;
+        }
+    }
+
     Ok((processed_leaves, deletion_metadata))
 }
 
+// Each directory starts off holding the totals for its direct children only.
+// Working from the deepest directories upwards, add each directory's totals
+// to its parent so that every directory covers everything beneath it.
+fn roll_up_directory_totals(root: &Path, directory_totals: &mut DirectoryTotalsMap) {
+    let mut directories: Vec<PathBuf> = directory_totals.keys().cloned().collect();
+    directories.sort_by_key(|path| Reverse(path.components().count()));
+
+    for directory in directories {
+        if directory == root {
+            continue;
+        }
+        let (size, totals) = directory_totals[&directory];
+        let parent_totals = directory
+            .parent()
+            .and_then(|parent| directory_totals.get_mut(parent));
+        if let Some((parent_size, parent_totals)) = parent_totals {
+            *parent_size += size;
+            parent_totals.add(&totals);
+        }
+    }
+}
+
 fn scan_folder_contents(
-    folder_path: &PathBuf,
+    folder_path: &Path,
     config: &PathConfig,
     deletion_metadata: &mut DeletionMetaData,
-) -> IoResult<PathVec> {
-    let mut directory_contents: PathVec = Vec::new();
+    folder_totals: &mut (u64, DirTotals),
+) -> IoResult<EntryVec> {
+    let mut directory_contents: EntryVec = Vec::new();
+    let (folder_size, totals) = folder_totals;
 
     for entry in fs::read_dir(folder_path)? {
         let entry = entry?;
         let entry_path = entry.path();
 
         if entry_path.is_dir() {
-            directory_contents.push(entry_path);
+            totals.dir_count += 1;
+            directory_contents.push(ScannedEntry::from_dir(entry_path));
         } else if entry_path.is_file() {
-            let should_delete = evaluate_file_for_deletion(&entry_path, config, deletion_metadata)?;
-            if should_delete {
-                directory_contents.push(entry_path);
+            let file_metadata = fs::metadata(&entry_path)?;
+            let verdict =
+                evaluate_file_for_deletion(&entry_path, &file_metadata, config, deletion_metadata);
+
+            *folder_size += file_metadata.len();
+            totals.file_count += 1;
+            if verdict.is_ok() {
+                totals.deletion_size += file_metadata.len();
+                totals.deletion_count += 1;
+            }
+
+            if verdict.is_ok() || config.track_kept_files {
+                directory_contents.push(ScannedEntry::from_file(
+                    entry_path,
+                    &file_metadata,
+                    verdict,
+                ));
             }
         }
     }
 
-    directory_contents.sort_unstable();
+    directory_contents.sort_unstable_by(|a, b| a.path.cmp(&b.path));
     Ok(directory_contents)
 }
 
 fn evaluate_file_for_deletion(
-    path: &PathBuf,
+    path: &Path,
+    file_metadata: &Metadata,
     config: &PathConfig,
     deletion_metadata: &mut DeletionMetaData,
-) -> IoResult<bool> {
-    let file_metadata = fs::metadata(&path)?;
+) -> Result<DeletionRule, KeepReason> {
     deletion_metadata.folder_size += file_metadata.len();
+    deletion_metadata.record_extension(path, file_metadata.len());
 
-    if should_delete_file(path, config) {
+    let verdict = classify_file(path, file_metadata.modified().ok(), config);
+    if verdict.is_ok() {
         deletion_metadata.deletion_size += file_metadata.len();
         deletion_metadata.file_count += 1;
+    }
+
+    verdict
+}
 
-        return Ok(true);
-    } else {
-        return Ok(false);
+/// Classifies every tracked file again against `config`, e.g. after the user has
+/// changed the filters, and recalculates the deletion totals without rescanning.
+///
+/// Files that were left out of the scan can't be brought back, so this is only
+/// accurate for stacks scanned with `track_kept_files` set.
+pub fn reclassify_tracked_files(
+    stack: &mut TreeQueue,
+    deletion_metadata: &mut DeletionMetaData,
+    config: &PathConfig,
+) {
+    let mut directory_indices: HashMap<PathBuf, usize> = HashMap::new();
+    for (index, leaf) in stack.iter_mut().enumerate() {
+        if leaf.is_dir {
+            leaf.totals.deletion_size = 0;
+            leaf.totals.deletion_count = 0;
+            directory_indices.insert(leaf.key.clone(), index);
+        }
     }
+
+    deletion_metadata.deletion_size = 0;
+    deletion_metadata.file_count = 0;
+    let mut counted_directories: HashSet<PathBuf> = HashSet::new();
+
+    for index in 0..stack.len() {
+        let leaf = &mut stack[index];
+        if leaf.is_dir {
+            if let Some(parent) = leaf.key.parent() {
+                counted_directories.insert(parent.to_path_buf());
+            }
+            continue;
+        }
+
+        let verdict = classify_file(&leaf.key, leaf.modified, config);
+        leaf.rule = verdict.clone().ok();
+        leaf.keep_reason = verdict.err();
+        if !leaf.is_scheduled_file() {
+            continue;
+        }
+
+        let (key, size) = (leaf.key.clone(), leaf.size);
+        deletion_metadata.deletion_size += size;
+        deletion_metadata.file_count += 1;
+        if let Some(parent) = key.parent() {
+            counted_directories.insert(parent.to_path_buf());
+        }
+        for ancestor in key.ancestors().skip(1) {
+            if let Some(&dir_index) = directory_indices.get(ancestor) {
+                stack[dir_index].totals.deletion_size += size;
+                stack[dir_index].totals.deletion_count += 1;
+            }
+        }
+    }
+
+    // As when scanning, only directories holding subfolders or scheduled files count.
+    deletion_metadata.dir_count = counted_directories
+        .iter()
+        .filter(|path| directory_indices.contains_key(*path))
+        .count();
 }
 
-fn create_tree_leaves_from_paths(paths: PathVec, depth: usize) -> TreeQueue {
+fn create_tree_leaves_from_paths(entries: EntryVec, depth: usize) -> TreeQueue {
     let mut path_leaves: TreeQueue = VecDeque::new();
-    let paths_len = paths.len();
 
-    for (index, path) in paths.into_iter().enumerate() {
+    for entry in entries {
         let file_leaf = DirTreeLeaf {
-            key: path,
+            key: entry.path,
             depth,
-            is_last: index == paths_len - 1,
+            is_dir: entry.is_dir,
+            size: entry.size,
+            modified: entry.modified,
+            rule: entry.rule,
+            keep_reason: entry.keep_reason,
+            ..Default::default()
         };
         path_leaves.push_back(file_leaf);
     }
@@ -1,4 +1,15 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::ArgValueCompleter;
+use std::path::PathBuf;
+
+use super::completion::{complete_group, complete_path_or_group};
+use crate::configs::parsing::ConfigFormat;
+use crate::configs::presets::PRESETS;
+use crate::logging::folder_tree_helpers::TreeSortOrder;
+use crate::logging::style::ColorChoice;
+
+// Directories holding more files than this are collapsed in the tree by default.
+const DEFAULT_COLLAPSE_AFTER: usize = 50;
 
 /// Cleans up folders based on a given path or configuration file.
 #[derive(Parser)]
@@ -6,18 +17,52 @@ use clap::{Parser, Subcommand};
     name = "folder_cleaner",
     about = "A safer file cleaner. Generate detailed insights \
         about your folder(s), so you can avoid accidentally deleting data.",
-    version = "1.0"
+    version = "1.0",
+    after_help = "To complete commands and group names as you type, add \
+        `source <(COMPLETE=bash folder_cleaner)` to your ~/.bashrc, or the same \
+        with `zsh` to your ~/.zshrc."
 )]
-pub struct CLI {
+pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// When to colour the output. `auto` colours it if it's going to a terminal
+    /// and `NO_COLOR` isn't set.
+    #[arg(long, value_name = "WHEN", value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Leave out emoji and decorative symbols.
+    #[arg(long, global = true)]
+    pub plain: bool,
+
+    /// The config file to use, instead of looking in `$FOLDER_CLEANER_CONFIG`, the XDG
+    /// config directory or `~/.nuke.toml`.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Let `.nuke.toml` files in the current project replace groups and profiles of
+    /// the same name from your own config.
+    #[arg(long, global = true)]
+    pub allow_project_overrides: bool,
 }
 
 #[derive(Parser)]
 pub struct DirectoryArgs {
-    /// The path or configuration key to use.
-    #[arg(required = true)]
-    pub path_or_config_key: String,
+    /// The paths or configuration keys to use. Keys may be globs, e.g. `build-*`.
+    #[arg(
+        required_unless_present_any = ["all", "tags"],
+        conflicts_with = "all",
+        add = ArgValueCompleter::new(complete_path_or_group)
+    )]
+    pub paths_or_config_keys: Vec<String>,
+
+    /// Scan every group in your config file, finishing with a summary of each group.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Also scan every folder in your config file labelled with this tag. Can be repeated.
+    #[arg(long = "tag", value_name = "TAG", conflicts_with = "all")]
+    pub tags: Vec<String>,
 
     /// Recursively scan all items within all subfolders 📁
     #[arg(short, default_value_t = false)]
@@ -27,9 +72,66 @@ pub struct DirectoryArgs {
     #[arg(short, default_value_t = false)]
     pub include_hidden: bool,
 
-    /// If true, all paths will be displayed as full paths.
+    /// Use the filters of a built-in preset, e.g. `--preset python-caches`. Run
+    /// `presets list` to see them all.
+    #[arg(long, value_name = "PRESET", value_parser = PRESETS.map(|preset| preset.name))]
+    pub preset: Option<String>,
+
+    /// If true, all paths in the tree will be displayed as full paths.
     #[arg(long, aliases = ["full", "fullpath"])]
     pub full_path: bool,
+
+    /// How the results should be reported.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Export the deletion candidates to a CSV file (or TSV, if the file ends in `.tsv`).
+    #[arg(long, value_name = "FILE")]
+    pub export: Option<PathBuf>,
+
+    /// Include a summary row for each directory in the export.
+    #[arg(long, requires = "export")]
+    pub export_dirs: bool,
+
+    #[clap(flatten)]
+    pub tree_args: TreeArgs,
+}
+
+/// Controls how the directory tree 🌲 is printed.
+#[derive(Parser)]
+#[command(next_help_heading = "Tree options")]
+pub struct TreeArgs {
+    /// Only print entries up to N levels below the scanned folder.
+    #[arg(long, value_name = "N")]
+    pub tree_depth: Option<usize>,
+
+    /// Only print directories, leaving out individual files.
+    #[arg(long)]
+    pub dirs_only: bool,
+
+    /// Also print the files that will be kept, marking each entry as deleted or kept
+    /// along with the reason.
+    #[arg(long, aliases = ["full-tree"])]
+    pub show_kept: bool,
+
+    /// The order in which the entries of each directory are printed.
+    #[arg(long, value_enum, default_value_t = TreeSortOrder::Name)]
+    pub sort: TreeSortOrder,
+
+    /// Fold the remaining files of a directory into a single line after N files. Use 0 to
+    /// print every file.
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_COLLAPSE_AFTER)]
+    pub collapse_after: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable overview.
+    Text,
+    /// A single JSON document covering every scanned folder.
+    Json,
+    /// Newline delimited JSON, streaming one record per file.
+    Ndjson,
 }
 
 #[derive(Parser)]
@@ -37,7 +139,8 @@ pub struct CleanArgs {
     #[clap(flatten)]
     pub directory_args: DirectoryArgs,
 
-    /// Automatically approve the deletion request.
+    /// Automatically approve the deletion request. Text output still
+    /// prompts, while with JSON output files are only deleted when this is set.
     #[arg(short)]
     pub yes: bool,
 }
@@ -50,6 +153,195 @@ pub struct SizeArgs {
     /// Print out the file system tree 🌲
     #[arg(short, long)]
     pub tree: bool,
+
+    /// List the N largest files and directories 🏆
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// Write a self-contained HTML report of the scanned tree to the given file.
+    #[arg(long, value_name = "FILE")]
+    pub html: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct BrowseArgs {
+    #[clap(flatten)]
+    pub directory_args: DirectoryArgs,
+}
+
+#[derive(Parser)]
+pub struct PresetsArgs {
+    #[command(subcommand)]
+    pub command: PresetsCommands,
+}
+
+#[derive(Subcommand)]
+pub enum PresetsCommands {
+    /// Describe every built-in preset and what it deletes.
+    List(PresetListArgs),
+}
+
+#[derive(Parser)]
+pub struct PresetListArgs {
+    /// How the presets should be reported.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Parser)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Create a starter config file by answering a few questions.
+    Init(InitArgs),
+    /// Check the config for mistakes, exiting with an error if any are found.
+    Validate(ValidateArgs),
+    /// List every group in your config, with a summary of its rules.
+    List(ListArgs),
+    /// Show every setting of the folders in a group, as they would be used.
+    Show(ShowArgs),
+    /// Add a folder to a group, creating the group if need be.
+    Add(AddArgs),
+    /// Remove a folder from a group, or the whole group.
+    Remove(RemoveArgs),
+    /// Change a setting of the folders in a group, e.g. `config set logs.older_than_days 14`.
+    Set(SetArgs),
+    /// Rewrite a config as TOML, YAML or JSON, e.g. `config convert --to yaml`.
+    Convert(ConvertArgs),
+}
+
+#[derive(Parser)]
+pub struct InitArgs {
+    /// Replace the config file if one already exists.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Parser)]
+pub struct ValidateArgs {
+    /// The file to check. Defaults to every config file that's merged together.
+    pub file: Option<PathBuf>,
+
+    /// Treat warnings as errors.
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(Parser)]
+pub struct ListArgs {
+    /// Print only the group names, one per line. Useful for shell completion.
+    #[arg(long, conflicts_with = "output")]
+    pub names_only: bool,
+
+    /// How the groups should be reported.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Parser)]
+pub struct ShowArgs {
+    /// The group to show. Globs such as `build-*` show every matching group.
+    #[arg(add = ArgValueCompleter::new(complete_group))]
+    pub group: String,
+
+    /// Show the settings as they'd be with -r passed to `clean` or `size`.
+    #[arg(short, default_value_t = false)]
+    pub recursive: bool,
+
+    /// Show the settings as they'd be with -i passed to `clean` or `size`.
+    #[arg(short, default_value_t = false)]
+    pub include_hidden: bool,
+
+    /// How the settings should be reported.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Parser)]
+pub struct AddArgs {
+    /// The group to add the folder to.
+    #[arg(add = ArgValueCompleter::new(complete_group))]
+    pub group: String,
+
+    /// The folder to clean.
+    pub directory: PathBuf,
+
+    /// An extension to delete. Can be repeated or given as a comma separated list.
+    #[arg(long = "ext", value_name = "EXT", value_delimiter = ',')]
+    pub extensions: Vec<String>,
+
+    /// An extension to always keep. Can be repeated or given as a comma separated list.
+    #[arg(long = "keep-ext", value_name = "EXT", value_delimiter = ',')]
+    pub keep_extensions: Vec<String>,
+
+    /// Only delete files last modified more than this many days ago.
+    #[arg(long, value_name = "DAYS")]
+    pub older_than: Option<u64>,
+
+    /// Label the folder with a tag, for use with `--tag`. Can be repeated.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Clean subfolders as well.
+    #[arg(short, default_value_t = false)]
+    pub recursive: bool,
+
+    /// Delete hidden files as well.
+    #[arg(short, default_value_t = false)]
+    pub include_hidden: bool,
+
+    /// Replace the settings of the folder if the group already has it.
+    #[arg(long)]
+    pub replace: bool,
+}
+
+#[derive(Parser)]
+pub struct RemoveArgs {
+    /// The group to remove, or to remove a folder from.
+    #[arg(add = ArgValueCompleter::new(complete_group))]
+    pub group: String,
+
+    /// Only remove this folder from the group.
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct SetArgs {
+    /// The setting to change, as `<group>.<field>`.
+    pub key: String,
+
+    /// The new value. Lists are comma separated, and an empty list or an age of 0
+    /// removes the setting.
+    #[arg(allow_hyphen_values = true)]
+    pub value: String,
+
+    /// Only change the folder in the group with this directory.
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct ConvertArgs {
+    /// The config to convert. Defaults to your config file.
+    pub file: Option<PathBuf>,
+
+    /// The format to convert to.
+    #[arg(long, value_enum)]
+    pub to: ConfigFormat,
+
+    /// Write the converted config to this file, instead of printing it. Comments in
+    /// the original aren't carried over.
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Replace the output file if it already exists.
+    #[arg(long, requires = "output")]
+    pub force: bool,
 }
 
 #[derive(Subcommand)]
@@ -60,6 +352,15 @@ pub enum Commands {
     /// Show the size of a directory based on a path or configuration key.
     Size(SizeArgs),
 
+    /// Explore a directory in a full-screen browser, marking and deleting files as you go.
+    Browse(BrowseArgs),
+
     /// Display the path to your configuration file.
     ConfigPath,
+
+    /// Create and manage your configuration file.
+    Config(ConfigArgs),
+
+    /// Browse the built-in cleaning presets.
+    Presets(PresetsArgs),
 }
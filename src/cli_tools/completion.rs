@@ -0,0 +1,44 @@
+use clap_complete::engine::{CompletionCandidate, PathCompleter, ValueCompleter};
+use std::ffi::OsStr;
+
+use crate::configs::layers::discover_config_layers;
+use crate::configs::location::resolve_config_path;
+use crate::configs::unwrap_config_groups::fetch_layered_configs;
+
+// The groups from the config the command would use, or none if it can't be read.
+// Naming a group deletes nothing, so project configs are allowed to add their own.
+fn group_names() -> Vec<String> {
+    let Ok(location) = resolve_config_path(None) else {
+        return Vec::new();
+    };
+    let layers = discover_config_layers(&location, true);
+    let configs = fetch_layered_configs(&layers, &location.path, None).unwrap_or_default();
+    let mut names: Vec<String> = configs
+        .into_iter()
+        .filter_map(|config| config.group)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn group_candidates(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    group_names()
+        .into_iter()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Completes the name of a group from the config.
+pub fn complete_group(current: &OsStr) -> Vec<CompletionCandidate> {
+    group_candidates(current)
+}
+
+/// Completes the name of a group from the config, followed by any matching paths.
+pub fn complete_path_or_group(current: &OsStr) -> Vec<CompletionCandidate> {
+    let mut candidates = group_candidates(current);
+    candidates.extend(PathCompleter::any().complete(current));
+    candidates
+}
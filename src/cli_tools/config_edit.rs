@@ -0,0 +1,180 @@
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+use super::cleaner_cli::{AddArgs, RemoveArgs, SetArgs};
+use super::config_init::parse_extension_list;
+use super::errors::CLIError;
+use crate::configs::config::PathConfig;
+use crate::configs::editing::{add_to_group, remove_from_group, set_in_group, Setting};
+
+/// The fields `config set` can change, as they're named in the config file.
+pub const SETTING_FIELDS: [&str; 9] = [
+    "directory",
+    "extensions_to_delete",
+    "extensions_to_keep",
+    "names_to_delete",
+    "recursive",
+    "delete_hidden",
+    "older_than_days",
+    "tags",
+    "profile",
+];
+
+fn invalid_setting(field: &str, value: &str, expected: &'static str) -> CLIError {
+    CLIError::InvalidSetting(field.to_string(), value.to_string(), expected)
+}
+
+fn parse_bool(field: &str, value: &str) -> Result<bool, CLIError> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(invalid_setting(field, value, "true or false")),
+    }
+}
+
+/// Splits `<group>.<field>` into its parts. Group names may contain dots, field names don't.
+pub fn parse_setting_key(key: &str) -> Result<(&str, &str), CLIError> {
+    key.rsplit_once('.')
+        .filter(|(group, field)| !group.is_empty() && SETTING_FIELDS.contains(field))
+        .ok_or_else(|| CLIError::UnknownSetting(key.to_string()))
+}
+
+/// Reads the value given to `config set` for a field. Empty lists and an age of 0
+/// remove the setting, leaving the default in place.
+pub fn parse_setting(field: &str, value: &str) -> Result<Setting, CLIError> {
+    let setting = match field {
+        "directory" => Setting::Directory(expand_directory(Path::new(value.trim()))),
+        "extensions_to_delete" => Setting::ExtensionsToDelete(parse_extension_list(value)),
+        "extensions_to_keep" => Setting::ExtensionsToKeep(parse_extension_list(value)),
+        "recursive" => Setting::Recursive(parse_bool(field, value)?),
+        "delete_hidden" => Setting::DeleteHidden(parse_bool(field, value)?),
+        "older_than_days" => {
+            let days: u64 = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid_setting(field, value, "a whole number of days"))?;
+            Setting::OlderThanDays(Some(days).filter(|days| *days > 0))
+        }
+        "names_to_delete" => {
+            Setting::NamesToDelete(Some(parse_list(value)).filter(|names| !names.is_empty()))
+        }
+        "tags" => Setting::Tags(parse_list(value)),
+        "profile" => {
+            Setting::Profile(Some(value.trim().to_string()).filter(|profile| !profile.is_empty()))
+        }
+        _ => return Err(CLIError::UnknownSetting(field.to_string())),
+    };
+    Ok(setting)
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Config files are read from anywhere, so relative paths are stored in full.
+fn expand_directory(directory: &Path) -> PathBuf {
+    canonicalize(directory).unwrap_or_else(|_| directory.to_path_buf())
+}
+
+fn existing_directory(directory: &Path) -> Result<PathBuf, CLIError> {
+    match directory.is_dir() {
+        true => Ok(expand_directory(directory)),
+        false => Err(CLIError::DirectoryNotFound(directory.to_path_buf())),
+    }
+}
+
+/// Adds a folder to a group, creating the group and the config file if need be. A
+/// folder the group already has is only replaced with `--replace`.
+pub fn run_config_add(args: &AddArgs, config_path: &Path) -> Result<(), CLIError> {
+    let mut config = PathConfig::new(existing_directory(&args.directory)?, true);
+    config.extensions_to_delete = parse_extension_list(&args.extensions.join(","));
+    config.extensions_to_keep = parse_extension_list(&args.keep_extensions.join(","));
+    config.older_than_days = args.older_than.filter(|days| *days > 0);
+    config.recursive = args.recursive;
+    config.delete_hidden = args.include_hidden;
+    config.tags = args.tags.clone();
+
+    add_to_group(config_path, &args.group, &config, args.replace)?;
+    println!(
+        "Saved {:?} to the group '{}' in {:?}.",
+        config.directory, args.group, config_path
+    );
+    Ok(())
+}
+
+/// Removes a folder, or a whole group, from the config.
+pub fn run_config_remove(args: &RemoveArgs, config_path: &Path) -> Result<(), CLIError> {
+    let directory = args.dir.as_deref().map(expand_directory);
+    let removed = remove_from_group(config_path, &args.group, directory.as_deref())?;
+    match directory {
+        Some(directory) => println!("Removed {:?} from the group '{}'.", directory, args.group),
+        None => println!(
+            "Removed the group '{}' and its {} folder(s).",
+            args.group, removed
+        ),
+    }
+    Ok(())
+}
+
+/// Changes a single setting of the folders in a group.
+pub fn run_config_set(args: &SetArgs, config_path: &Path) -> Result<(), CLIError> {
+    let (group, field) = parse_setting_key(&args.key)?;
+    let setting = parse_setting(field, &args.value)?;
+    let directory = args.dir.as_deref().map(expand_directory);
+
+    let changed = set_in_group(config_path, group, directory.as_deref(), &setting)?;
+    println!(
+        "Set {} for {} folder(s) in the group '{}'.",
+        field, changed, group
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_setting_key() {
+        assert_eq!(
+            parse_setting_key("logs.older_than_days").unwrap(),
+            ("logs", "older_than_days")
+        );
+        assert_eq!(
+            parse_setting_key("team.v2.tags").unwrap(),
+            ("team.v2", "tags")
+        );
+        assert!(parse_setting_key("logs.extensions").is_err());
+        assert!(parse_setting_key("logs").is_err());
+    }
+
+    #[test]
+    fn test_parse_setting_values() {
+        assert_eq!(
+            parse_setting("extensions_to_delete", ".LOG, tmp").unwrap(),
+            Setting::ExtensionsToDelete(Some(vec!["log".to_string(), "tmp".to_string()]))
+        );
+        assert_eq!(
+            parse_setting("extensions_to_keep", "").unwrap(),
+            Setting::ExtensionsToKeep(None)
+        );
+        assert_eq!(
+            parse_setting("older_than_days", "0").unwrap(),
+            Setting::OlderThanDays(None)
+        );
+        assert_eq!(
+            parse_setting("delete_hidden", "yes").unwrap(),
+            Setting::DeleteHidden(true)
+        );
+        assert!(matches!(
+            parse_setting("recursive", "sometimes"),
+            Err(CLIError::InvalidSetting(_, _, _))
+        ));
+        assert!(parse_setting("older_than_days", "-3").is_err());
+    }
+}
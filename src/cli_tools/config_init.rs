@@ -0,0 +1,242 @@
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, InquireError, Select, Text};
+use std::fmt;
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+use super::errors::CLIError;
+use super::refine::add_extension;
+use crate::configs::config::PathConfig;
+use crate::configs::editing::{render_new_config, write_config_file, NewConfigEntry};
+use crate::configs::errors::ConfigError;
+use crate::configs::get_user_home_dir;
+use crate::configs::parsing::{
+    convert_config, is_reserved_group_name, parse_config_from_str, ConfigFormat, ConfigParseError,
+};
+use crate::configs::presets::{Preset, PRESETS};
+
+// A starting point for a folder's filters: one of the presets, or a blank slate.
+enum PresetChoice {
+    Preset(&'static Preset),
+    Custom,
+}
+
+impl fmt::Display for PresetChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetChoice::Preset(preset) => write!(f, "{} - {}", preset.name, preset.description),
+            PresetChoice::Custom => write!(f, "custom - Choose the extensions yourself"),
+        }
+    }
+}
+
+impl PresetChoice {
+    fn preset(&self) -> Option<&'static Preset> {
+        match self {
+            PresetChoice::Preset(preset) => Some(preset),
+            PresetChoice::Custom => None,
+        }
+    }
+}
+
+/// Splits a comma or space separated list of extensions, dropping duplicates and
+/// leading dots. An empty list gives `None`.
+pub fn parse_extension_list(input: &str) -> Option<Vec<String>> {
+    let mut extensions = None;
+    for extension in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        add_extension(&mut extensions, extension);
+    }
+    extensions
+}
+
+fn prompt_directory(preset: Option<&Preset>) -> Result<PathBuf, InquireError> {
+    let suggested = preset
+        .and_then(|preset| preset.suggested_directory)
+        .and_then(|directory| Some(get_user_home_dir().ok()?.join(directory)))
+        .filter(|directory| directory.is_dir())
+        .map(|directory| directory.to_string_lossy().to_string());
+
+    let mut prompt =
+        Text::new("Which folder should be cleaned?").with_validator(|input: &str| match Path::new(
+            input.trim(),
+        )
+        .is_dir()
+        {
+            true => Ok(Validation::Valid),
+            false => Ok(Validation::Invalid("That folder doesn't exist".into())),
+        });
+    if let Some(suggested) = suggested.as_deref() {
+        prompt = prompt.with_default(suggested);
+    }
+
+    let directory = PathBuf::from(prompt.prompt()?.trim());
+    // Config files are read from anywhere, so relative paths are stored in full.
+    Ok(canonicalize(&directory).unwrap_or(directory))
+}
+
+fn prompt_group_name(default: &str) -> Result<String, InquireError> {
+    Text::new("Which group should it belong to?")
+        .with_default(default)
+        .with_help_message("You'll use this name to clean the folder, e.g. `clean downloads`")
+        .with_validator(|input: &str| match input.trim() {
+            "" => Ok(Validation::Invalid("Enter a group name".into())),
+            name if is_reserved_group_name(name) => Ok(Validation::Invalid(
+                format!(
+                    "`{}` is used by the config's own settings, pick another name",
+                    name
+                )
+                .into(),
+            )),
+            _ => Ok(Validation::Valid),
+        })
+        .prompt()
+        .map(|group| group.trim().to_string())
+}
+
+fn prompt_folder_entry() -> Result<NewConfigEntry, InquireError> {
+    let mut choices: Vec<PresetChoice> = PRESETS.iter().map(PresetChoice::Preset).collect();
+    choices.push(PresetChoice::Custom);
+    let choice = Select::new("What would you like to clean?", choices).prompt()?;
+    let preset = choice.preset();
+
+    let directory = prompt_directory(preset)?;
+    let default_group = match preset {
+        Some(preset) => preset.name.to_string(),
+        None => directory
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    let group = prompt_group_name(&default_group)?;
+
+    let default_extensions = preset
+        .map(|preset| preset.extensions_to_delete.join(", "))
+        .unwrap_or_default();
+    let mut extensions_prompt = Text::new("Which extensions should be deleted?")
+        .with_default(&default_extensions)
+        .with_help_message("Separated by commas. Leave empty to delete files of any extension");
+    // Without a preset's names to go on, an empty list would delete everything.
+    if preset.is_none() {
+        extensions_prompt = extensions_prompt
+            .with_help_message("Separated by commas, e.g. `log, tmp`")
+            .with_validator(|input: &str| match parse_extension_list(input) {
+                Some(_) => Ok(Validation::Valid),
+                None => Ok(Validation::Invalid("Enter at least one extension".into())),
+            });
+    }
+    let extensions_to_delete = extensions_prompt.prompt()?;
+    let extensions_to_keep = Text::new("Which extensions should always be kept?")
+        .with_help_message("Separated by commas. Leave empty to keep nothing in particular")
+        .prompt()?;
+    let older_than_days = CustomType::<u64>::new("Only delete files older than how many days?")
+        .with_default(
+            preset
+                .and_then(|preset| preset.older_than_days)
+                .unwrap_or(0),
+        )
+        .with_help_message("0 deletes files of any age")
+        .with_error_message("Enter a whole number of days")
+        .prompt()?;
+    let delete_hidden = Confirm::new("Should hidden files be deleted too?")
+        .with_default(preset.is_some_and(|preset| preset.delete_hidden))
+        .prompt()?;
+
+    let mut config = PathConfig::new(directory, true);
+    config.extensions_to_delete = parse_extension_list(&extensions_to_delete);
+    config.extensions_to_keep = parse_extension_list(&extensions_to_keep);
+    config.older_than_days = Some(older_than_days).filter(|days| *days > 0);
+    config.delete_hidden = delete_hidden;
+    if let Some(preset) = preset {
+        config.names_to_delete = preset.settings().names_to_delete;
+        config.recursive = preset.recursive;
+    }
+
+    Ok(NewConfigEntry {
+        group,
+        config,
+        comment: preset.map(|preset| preset.description.to_string()),
+    })
+}
+
+// The starter config is written as TOML, so other formats lose its comments. It's read
+// back before it's written, so the wizard can't leave behind a config that won't load.
+fn render_config_as(
+    entries: &[NewConfigEntry],
+    format: ConfigFormat,
+) -> Result<String, ConfigParseError> {
+    let contents = match format {
+        ConfigFormat::Toml => render_new_config(entries),
+        format => convert_config(&render_new_config(entries), ConfigFormat::Toml, format)?,
+    };
+    parse_config_from_str(&contents, format)?;
+    Ok(contents)
+}
+
+/// Walks the user through creating a starter config, then writes it to `config_path`.
+///
+/// # Arguments
+///
+/// * `config_path` - Where to write the config.
+/// * `force` - Whether to replace a config that already exists.
+pub fn run_config_init(config_path: &Path, force: bool) -> Result<(), CLIError> {
+    if config_path.exists() && !force {
+        return Err(ConfigError::AlreadyExists(config_path.to_path_buf()).into());
+    }
+    println!(
+        "Let's set up the folders you'd like to clean. Your config will be written to {:?}.",
+        config_path
+    );
+
+    let mut entries = Vec::new();
+    loop {
+        entries.push(prompt_folder_entry()?);
+        let add_another = Confirm::new("Would you like to add another folder?")
+            .with_default(false)
+            .prompt()?;
+        if !add_another {
+            break;
+        }
+    }
+
+    let contents = render_config_as(&entries, ConfigFormat::from_path(config_path))
+        .map_err(|e| ConfigError::parse_error(config_path, e))?;
+    write_config_file(config_path, &contents)?;
+    println!(
+        "Your config was written to {:?}. Try `folder_cleaner size {}` to see what would be cleaned.",
+        config_path, entries[0].group
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_extension_list() {
+        assert_eq!(
+            parse_extension_list(".log, tmp  OUT,,log"),
+            Some(vec![
+                "log".to_string(),
+                "tmp".to_string(),
+                "out".to_string()
+            ])
+        );
+        assert_eq!(parse_extension_list("  "), None);
+    }
+
+    #[test]
+    fn test_rendered_configs_are_read_back() {
+        let entry = |group: &str| NewConfigEntry {
+            group: group.to_string(),
+            config: PathConfig::new(PathBuf::from("/tmp"), true),
+            comment: None,
+        };
+        for format in [ConfigFormat::Toml, ConfigFormat::Yaml, ConfigFormat::Json] {
+            assert!(render_config_as(&[entry("downloads")], format).is_ok());
+            assert!(render_config_as(&[entry("defaults")], format).is_err());
+        }
+        assert!(is_reserved_group_name("profiles"));
+        assert!(!is_reserved_group_name("downloads"));
+    }
+}
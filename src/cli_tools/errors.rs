@@ -1,10 +1,18 @@
+use super::config_edit::SETTING_FIELDS;
 use crate::configs::errors::ConfigError;
+use inquire::InquireError;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum CLIError {
     PathOrConfigError(String),
+    TagNotFound(String),
     ConfigError(ConfigError),
+    PromptError(InquireError),
+    DirectoryNotFound(PathBuf),
+    UnknownSetting(String),
+    InvalidSetting(String, String, &'static str),
 }
 
 impl From<ConfigError> for CLIError {
@@ -13,6 +21,12 @@ impl From<ConfigError> for CLIError {
     }
 }
 
+impl From<InquireError> for CLIError {
+    fn from(error: InquireError) -> Self {
+        CLIError::PromptError(error)
+    }
+}
+
 impl fmt::Display for CLIError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -24,7 +38,25 @@ impl fmt::Display for CLIError {
                     path
                 )
             }
+            CLIError::TagNotFound(tag) => {
+                write!(f, "No folder in your config is tagged with '{}'.", tag)
+            }
             CLIError::ConfigError(error) => write!(f, "{}", error),
+            CLIError::PromptError(error) => write!(f, "{}", error),
+            CLIError::DirectoryNotFound(directory) => {
+                write!(f, "The folder '{}' doesn't exist.", directory.display())
+            }
+            CLIError::UnknownSetting(key) => write!(
+                f,
+                "'{}' isn't a setting. Use <group>.<field>, where the field is one of: {}.",
+                key,
+                SETTING_FIELDS.join(", ")
+            ),
+            CLIError::InvalidSetting(field, value, expected) => write!(
+                f,
+                "'{}' isn't a valid value for {}. Expected {}.",
+                value, field, expected
+            ),
         }
     }
 }
@@ -1,26 +1,138 @@
 mod cleaner_cli;
+mod completion;
+mod config_edit;
+mod config_init;
 mod errors;
+mod refine;
+mod review;
 mod subprompts;
 mod validation;
 
-use crate::cleaning::track_files_for_deletion_in_given_config;
+use crate::browser::browse_folder;
+use crate::cleaning::track_files_for_deletion::DeletionMetaData;
+use crate::cleaning::{
+    delete_files_scheduled_for_deletion, prune_matched_directories,
+    track_files_for_deletion_in_given_config,
+};
 use crate::configs::config::PathConfig;
-use crate::configs::get_user_config_path;
-use crate::configs::unwrap_config_groups::fetch_cli_configs;
-use crate::configs::{report_user_config_path, CONFIG_FILE_NAME};
-use crate::logging::TextOverviewType;
-use clap::Parser;
-use cleaner_cli::{CleanArgs, Commands, DirectoryArgs, SizeArgs, CLI};
+use crate::configs::editing::write_config_file;
+use crate::configs::errors::ConfigError;
+use crate::configs::get_user_home_dir;
+use crate::configs::layers::{discover_config_layers, ConfigLayer, LayerKind};
+use crate::configs::location::resolve_config_path;
+use crate::configs::parsing::{convert_config, ConfigFormat};
+use crate::configs::presets::{find_preset, PRESETS};
+use crate::configs::report_user_config_path;
+use crate::configs::unwrap_config_groups::{
+    configs_in_matching_groups, configs_with_tag, dedupe_directories, expand_folders,
+    fetch_layered_configs, UnexpandedFolder,
+};
+use crate::configs::validation::{validate_config_layer, Severity};
+use crate::logging::config_listing::{
+    generate_folder_config_json, generate_folder_config_ndjson, generate_folder_config_text,
+    generate_group_list_json, generate_group_list_ndjson, generate_group_list_text, ResolvedFolder,
+};
+use crate::logging::csv_export::CandidateExporter;
+use crate::logging::folder_tree_helpers::DirTreeOptions;
+use crate::logging::group_summary::GroupSummary;
+use crate::logging::html_report::{generate_html_report, HtmlReportFolder};
+use crate::logging::machine_output::{generate_json_report, generate_ndjson_records, FolderReport};
+use crate::logging::preset_listing::{
+    generate_preset_list_json, generate_preset_list_ndjson, generate_preset_list_text,
+};
+use crate::logging::process_directory_tree::FileSystemStack;
+use crate::logging::style::init_style;
+use crate::logging::validation_report::generate_validation_text;
+use crate::logging::{print_directory_tree, print_largest_entries, TextOverviewType};
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
+use cleaner_cli::{
+    BrowseArgs, CleanArgs, Cli, Commands, ConfigArgs, ConfigCommands, ConvertArgs, DirectoryArgs,
+    ListArgs, OutputFormat, PresetsArgs, PresetsCommands, ShowArgs, SizeArgs, ValidateArgs,
+};
+use config_edit::{run_config_add, run_config_remove, run_config_set};
+use config_init::run_config_init;
 use errors::CLIError;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
-use subprompts::prompt_user_decision;
+use subprompts::{prompt_user_decision, PromptContext};
 use validation::validate_file_path;
 
+// The config files folders are read from, and the one new settings are saved to.
+struct ConfigFiles {
+    layers: Vec<ConfigLayer>,
+    user_path: PathBuf,
+}
+
+// Everything needed to scan and report on a set of folders.
+struct ScanRequest {
+    configs: Vec<PathConfig>,
+    overview_type: TextOverviewType,
+    output: OutputFormat,
+    auto_approve: bool,
+    export_path: Option<PathBuf>,
+    export_directories: bool,
+    html_path: Option<PathBuf>,
+    top_entries: Option<usize>,
+    print_tree: bool,
+    tree_options: DirTreeOptions,
+    summarise_groups: bool,
+    config_path: PathBuf,
+}
+
+impl ScanRequest {
+    fn new(
+        configs: Vec<PathConfig>,
+        overview_type: TextOverviewType,
+        directory_args: &DirectoryArgs,
+        config_path: &Path,
+    ) -> ScanRequest {
+        let tree_options = build_tree_options(directory_args, &overview_type);
+        ScanRequest {
+            configs,
+            overview_type,
+            output: directory_args.output,
+            auto_approve: false,
+            export_path: directory_args.export.clone(),
+            export_directories: directory_args.export_dirs,
+            html_path: None,
+            top_entries: None,
+            print_tree: false,
+            tree_options,
+            summarise_groups: directory_args.all,
+            config_path: config_path.to_path_buf(),
+        }
+    }
+}
+
+fn build_tree_options(
+    directory_args: &DirectoryArgs,
+    overview_type: &TextOverviewType,
+) -> DirTreeOptions {
+    let tree_args = &directory_args.tree_args;
+    DirTreeOptions {
+        display_files: !tree_args.dirs_only,
+        show_sizes: true,
+        // Deletion trees also show how much of each directory is scheduled.
+        show_scheduled: matches!(overview_type, TextOverviewType::Deletion),
+        show_status: tree_args.show_kept,
+        show_deleted: matches!(overview_type, TextOverviewType::Deletion),
+        show_kept: tree_args.show_kept,
+        max_depth: tree_args.tree_depth,
+        sort: tree_args.sort,
+        collapse_after: Some(tree_args.collapse_after).filter(|limit| *limit > 0),
+        full_paths: directory_args.full_path,
+    }
+}
+
 pub fn run_cli() {
+    // Answers the shell when it asks for completions, e.g. `COMPLETE=bash folder_cleaner`
+    CompleteEnv::with_factory(Cli::command).complete();
     match parse_cli_arguments() {
-        Ok((configs, text_overview)) => {
-            scan_folders(configs, text_overview);
+        Ok(scan_request) => {
+            scan_folders(scan_request);
         }
         Err(e) => {
             eprintln!("Error: {}", e);
@@ -29,51 +141,294 @@ pub fn run_cli() {
     }
 }
 
-fn parse_cli_arguments() -> Result<(Vec<PathConfig>, TextOverviewType), CLIError> {
-    let cli_args = CLI::parse();
+fn parse_cli_arguments() -> Result<ScanRequest, CLIError> {
+    let cli_args = Cli::parse();
+    init_style(cli_args.color, cli_args.plain);
+    let config_location = resolve_config_path(cli_args.config.as_deref())?;
+    let config_files = ConfigFiles {
+        layers: discover_config_layers(&config_location, cli_args.allow_project_overrides),
+        user_path: config_location.path.clone(),
+    };
 
     match cli_args.command {
         Commands::ConfigPath => {
-            report_user_config_path(CONFIG_FILE_NAME);
+            report_user_config_path(&config_location, &config_files.layers);
             process::exit(0); // Exit the program after reporting the path.
         }
+        Commands::Config(args) => {
+            handle_config_args(&args, &config_files)?;
+            process::exit(0);
+        }
+        Commands::Presets(args) => {
+            handle_presets_args(&args);
+            process::exit(0);
+        }
         // Returns a result
-        Commands::Clean(args) => handle_cleaner_args(&args),
-        Commands::Size(args) => handle_size_args(&args),
+        Commands::Clean(args) => handle_cleaner_args(&args, &config_files),
+        Commands::Size(args) => handle_size_args(&args, &config_files),
+        Commands::Browse(args) => {
+            browse_folders(&args, &config_files)?;
+            process::exit(0); // The browser handles any deletions itself.
+        }
+    }
+}
+
+fn handle_config_args(args: &ConfigArgs, config_files: &ConfigFiles) -> Result<(), CLIError> {
+    match &args.command {
+        ConfigCommands::Init(init_args) => {
+            run_config_init(&config_files.user_path, init_args.force)
+        }
+        ConfigCommands::Validate(validate_args) => {
+            validate_config_files(validate_args, config_files)
+        }
+        ConfigCommands::List(list_args) => list_config_groups(list_args, config_files),
+        ConfigCommands::Show(show_args) => show_config_group(show_args, config_files),
+        ConfigCommands::Add(add_args) => run_config_add(add_args, &config_files.user_path),
+        ConfigCommands::Remove(remove_args) => {
+            run_config_remove(remove_args, &config_files.user_path)
+        }
+        ConfigCommands::Set(set_args) => run_config_set(set_args, &config_files.user_path),
+        ConfigCommands::Convert(convert_args) => {
+            convert_config_file(convert_args, &config_files.user_path)
+        }
     }
 }
 
-fn handle_cleaner_args(args: &CleanArgs) -> Result<(Vec<PathConfig>, TextOverviewType), CLIError> {
-    let configs = get_and_update_path_config(&args.directory_args)?;
-    Ok((configs, TextOverviewType::Deletion))
+fn convert_config_file(args: &ConvertArgs, user_path: &Path) -> Result<(), CLIError> {
+    let source = args.file.as_deref().unwrap_or(user_path);
+    let content = fs::read_to_string(source).map_err(|_| ConfigError::read_error(source))?;
+    let converted = convert_config(&content, ConfigFormat::from_path(source), args.to)
+        .map_err(|e| ConfigError::parse_error(source, e))?;
+
+    let Some(output) = &args.output else {
+        print!("{}", converted);
+        return Ok(());
+    };
+    if output.exists() && !args.force {
+        return Err(ConfigError::AlreadyExists(output.clone()).into());
+    }
+    // The output's own extension decides how it's read back, so a mismatch is flagged.
+    if ConfigFormat::from_path(output) != args.to {
+        eprintln!(
+            "Warning: {:?} will be read as {}, not {}. Consider naming it `*.{}`.",
+            output,
+            ConfigFormat::from_path(output),
+            args.to,
+            args.to.extension()
+        );
+    }
+    write_config_file(output, &converted)?;
+    println!("Converted {:?} to {} in {:?}.", source, args.to, output);
+    Ok(())
 }
 
-fn handle_size_args(args: &SizeArgs) -> Result<(Vec<PathConfig>, TextOverviewType), CLIError> {
-    let configs = get_and_update_path_config(&args.directory_args)?;
-    Ok((configs, TextOverviewType::Size))
+fn handle_presets_args(args: &PresetsArgs) {
+    match &args.command {
+        PresetsCommands::List(list_args) => {
+            let text = match list_args.output {
+                OutputFormat::Text => generate_preset_list_text(&PRESETS),
+                OutputFormat::Json => generate_preset_list_json(&PRESETS),
+                OutputFormat::Ndjson => generate_preset_list_ndjson(&PRESETS),
+            };
+            println!("{}", text);
+        }
+    }
 }
 
-fn get_and_update_path_config(args: &DirectoryArgs) -> Result<Vec<PathConfig>, CLIError> {
-    let configs = get_path_config_from_key(&args.path_or_config_key, args.full_path)?;
-    let updated_configs = update_configs_with_cli_args(configs, &args);
+fn list_config_groups(args: &ListArgs, config_files: &ConfigFiles) -> Result<(), CLIError> {
+    let configs = match get_all_path_configs(config_files) {
+        // Completion scripts call this on every tab, so a missing config just means no groups.
+        Err(_) if args.names_only => return Ok(()),
+        result => result?,
+    };
+    if args.names_only {
+        let groups: BTreeSet<String> = configs
+            .into_iter()
+            .filter_map(|config| config.group)
+            .collect();
+        groups.iter().for_each(|group| println!("{}", group));
+        return Ok(());
+    }
+
+    // Folders that can't be expanded are still listed, as they're written.
+    let (mut configs, unexpanded) = expand_folders(configs);
+    for folder in unexpanded {
+        warn_unexpanded(&folder);
+        configs.push(folder.config);
+    }
+    let mut groups: BTreeMap<String, Vec<PathConfig>> = BTreeMap::new();
+    for config in configs {
+        let group = config.group.clone().unwrap_or_default();
+        groups.entry(group).or_default().push(config);
+    }
+    let text = match args.output {
+        OutputFormat::Text => generate_group_list_text(&groups),
+        OutputFormat::Json => generate_group_list_json(&groups),
+        OutputFormat::Ndjson => generate_group_list_ndjson(&groups),
+    };
+    println!("{}", text);
+    Ok(())
+}
+
+// Applies the same flags `clean` and `size` would, noting which ones changed anything.
+fn resolve_folder(mut config: PathConfig, args: &ShowArgs) -> ResolvedFolder {
+    let overrides = apply_cli_overrides(&mut config, args.recursive, args.include_hidden);
+    if let Ok(directory) = fs::canonicalize(&config.directory) {
+        config.directory = directory;
+    }
+    ResolvedFolder { config, overrides }
+}
+
+fn show_config_group(args: &ShowArgs, config_files: &ConfigFiles) -> Result<(), CLIError> {
+    let configs = get_all_path_configs(config_files)?;
+    let matched = configs_in_matching_groups(&configs, &args.group);
+    if matched.is_empty() {
+        return Err(ConfigError::FolderMapEmpty(args.group.clone()).into());
+    }
+    let matched = expand_and_warn(matched);
+
+    let folders: Vec<ResolvedFolder> = matched
+        .into_iter()
+        .map(|config| resolve_folder(config, args))
+        .collect();
+    let text = match args.output {
+        OutputFormat::Text => generate_folder_config_text(&folders),
+        OutputFormat::Json => generate_folder_config_json(&folders),
+        OutputFormat::Ndjson => generate_folder_config_ndjson(&folders),
+    };
+    println!("{}", text);
+    Ok(())
+}
+
+fn validate_config_files(args: &ValidateArgs, config_files: &ConfigFiles) -> Result<(), CLIError> {
+    let layers = match &args.file {
+        Some(file) => vec![ConfigLayer::new(file.clone(), LayerKind::User)],
+        None => config_files.layers.clone(),
+    };
+    if layers.is_empty() {
+        return Err(ConfigError::read_error(&config_files.user_path).into());
+    }
+
+    let home_dir = get_user_home_dir().ok();
+    let mut problems = 0;
+    for layer in &layers {
+        let report = validate_config_layer(layer, home_dir.as_deref())?;
+        println!("{}", generate_validation_text(&report));
+        problems += report.count(Severity::Error);
+        if args.strict {
+            problems += report.count(Severity::Warning);
+        }
+    }
+
+    match problems {
+        0 => Ok(()),
+        _ => Err(ConfigError::ValidationFailed(problems).into()),
+    }
+}
+
+fn browse_folders(args: &BrowseArgs, config_files: &ConfigFiles) -> Result<(), CLIError> {
+    let configs = get_and_update_path_config(&args.directory_args, config_files)?;
+    let folder_count = configs.len();
+
+    for (index, mut config) in configs.into_iter().enumerate() {
+        // The browser shows everything, with the candidates highlighted.
+        config.track_kept_files = true;
+
+        let (stack, _) = match track_files_for_deletion_in_given_config(&config) {
+            Ok(scanned) => scanned,
+            Err(_) => process::exit(1),
+        };
+        let title = match folder_count {
+            1 => String::new(),
+            _ => format!("[{}/{}]", index + 1, folder_count),
+        };
+        if let Err(e) = browse_folder(stack, title) {
+            eprintln!("Failed to run the browser: {}", e);
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn handle_cleaner_args(
+    args: &CleanArgs,
+    config_files: &ConfigFiles,
+) -> Result<ScanRequest, CLIError> {
+    let mut configs = get_and_update_path_config(&args.directory_args, config_files)?;
+    // Refining the filters from the prompt reclassifies the scanned files in
+    // memory, which needs the kept files as well as the candidates.
+    if args.directory_args.output == OutputFormat::Text {
+        configs
+            .iter_mut()
+            .for_each(|config| config.track_kept_files = true);
+    }
+    let mut request = ScanRequest::new(
+        configs,
+        TextOverviewType::Deletion,
+        &args.directory_args,
+        &config_files.user_path,
+    );
+    request.auto_approve = args.yes;
+    Ok(request)
+}
+
+fn handle_size_args(args: &SizeArgs, config_files: &ConfigFiles) -> Result<ScanRequest, CLIError> {
+    let mut configs = get_and_update_path_config(&args.directory_args, config_files)?;
+    // The HTML report covers the whole folder, not just the files matching the filters.
+    if args.html.is_some() {
+        configs
+            .iter_mut()
+            .for_each(|config| config.track_kept_files = true);
+    }
+    let mut request = ScanRequest::new(
+        configs,
+        TextOverviewType::Size,
+        &args.directory_args,
+        &config_files.user_path,
+    );
+    request.html_path = args.html.clone();
+    request.top_entries = args.top;
+    request.print_tree = args.tree;
+    Ok(request)
+}
+
+fn get_and_update_path_config(
+    args: &DirectoryArgs,
+    config_files: &ConfigFiles,
+) -> Result<Vec<PathConfig>, CLIError> {
+    let configs = match args.all {
+        true => dedupe_and_warn(expand_and_warn(get_all_path_configs(config_files)?)),
+        false => select_path_configs(&args.paths_or_config_keys, &args.tags, config_files)?,
+    };
+    let updated_configs = update_configs_with_cli_args(configs, args);
 
     Ok(updated_configs)
 }
 
 // TODO: Add strategy pattern here - instant deletion + prompt for deletion
-// TODO: Handle dir_tree requested => print_directory_tree
-fn scan_folders(configs: Vec<PathConfig>, overview_type: TextOverviewType) {
-    for config in configs.iter() {
-        match track_files_for_deletion_in_given_config(config, &overview_type) {
-            Ok((text_summary, file_folder_queue)) => {
-                println!("{}", text_summary);
-                match overview_type {
-                    TextOverviewType::Deletion => {
-                        let user_command = prompt_user_decision(&overview_type);
-                        user_command.process_command(file_folder_queue);
-                    }
-                    _ => {}
+fn scan_folders(request: ScanRequest) {
+    let mut folder_reports: Vec<FolderReport> = Vec::new();
+    let mut html_folders: Vec<HtmlReportFolder> = Vec::new();
+    let mut exporter = create_candidate_exporter(&request);
+    let mut group_summary = GroupSummary::new();
+
+    for config in request.configs.iter() {
+        match track_files_for_deletion_in_given_config(config) {
+            Ok((file_folder_queue, metadata)) => {
+                group_summary.add_folder(config, &metadata);
+                if let Some(exporter) = exporter.as_mut() {
+                    export_candidates(exporter, config, &file_folder_queue);
+                }
+                if request.html_path.is_some() {
+                    html_folders.push(HtmlReportFolder::new(config, &metadata, &file_folder_queue));
                 }
+                report_scanned_folder(
+                    config,
+                    &file_folder_queue,
+                    &metadata,
+                    &request,
+                    &mut folder_reports,
+                );
             }
             Err(e) => {
                 eprintln!("Failed to process the configuration for deletion: {}", e);
@@ -81,38 +436,298 @@ fn scan_folders(configs: Vec<PathConfig>, overview_type: TextOverviewType) {
             }
         }
     }
+
+    if request.output == OutputFormat::Json {
+        let command = request.overview_type.command_name();
+        println!("{}", generate_json_report(command, &folder_reports));
+    }
+
+    if let Some(html_path) = &request.html_path {
+        write_html_report(html_path, &html_folders);
+    }
+
+    if request.summarise_groups && request.output == OutputFormat::Text {
+        println!("{}", group_summary.generate_text());
+    }
+}
+
+fn write_html_report(html_path: &Path, html_folders: &[HtmlReportFolder]) {
+    if let Err(e) = fs::write(html_path, generate_html_report(html_folders)) {
+        eprintln!("Failed to write the HTML report to {:?}: {}", html_path, e);
+        process::exit(1);
+    }
+}
+
+fn create_candidate_exporter(request: &ScanRequest) -> Option<CandidateExporter> {
+    let export_path = request.export_path.as_ref()?;
+    match CandidateExporter::create(export_path, request.export_directories) {
+        Ok(exporter) => Some(exporter),
+        Err(e) => {
+            eprintln!("Failed to create export file {:?}: {}", export_path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn export_candidates(
+    exporter: &mut CandidateExporter,
+    config: &PathConfig,
+    file_folder_queue: &FileSystemStack,
+) {
+    if let Err(e) = exporter.write_folder(config, file_folder_queue) {
+        eprintln!("Failed to export the deletion candidates: {}", e);
+        process::exit(1);
+    }
+}
+
+fn report_scanned_folder(
+    config: &PathConfig,
+    file_folder_queue: &FileSystemStack,
+    metadata: &DeletionMetaData,
+    request: &ScanRequest,
+    folder_reports: &mut Vec<FolderReport>,
+) {
+    match request.output {
+        OutputFormat::Text => {
+            report_text_overview(config, file_folder_queue, metadata, request);
+        }
+        OutputFormat::Json => {
+            let report = build_folder_report(config, file_folder_queue, metadata, request);
+            folder_reports.push(report);
+        }
+        OutputFormat::Ndjson => {
+            let report = build_folder_report(config, file_folder_queue, metadata, request);
+            let command = request.overview_type.command_name();
+            println!("{}", generate_ndjson_records(command, &report));
+        }
+    }
+}
+
+fn report_text_overview(
+    config: &PathConfig,
+    file_folder_queue: &FileSystemStack,
+    metadata: &DeletionMetaData,
+    request: &ScanRequest,
+) {
+    println!("{}", request.overview_type.generate_text(config, metadata));
+    if request.print_tree {
+        print_directory_tree(file_folder_queue, &request.tree_options);
+    }
+    if let Some(limit) = request.top_entries {
+        print_largest_entries(file_folder_queue, limit);
+    }
+
+    if let TextOverviewType::Deletion = request.overview_type {
+        let mut context = PromptContext::new(
+            config.clone(),
+            file_folder_queue.clone(),
+            metadata.clone(),
+            &request.overview_type,
+            &request.tree_options,
+            &request.config_path,
+        );
+        // Keep prompting until the user picks an option that ends the session.
+        loop {
+            let user_command = prompt_user_decision(&request.overview_type);
+            user_command.process_command(&mut context);
+            if !user_command.returns_to_prompt() {
+                break;
+            }
+        }
+    }
+}
+
+// Machine readable output can't be mixed with interactive prompts, so files
+// are only deleted when the user has approved the deletion upfront.
+fn build_folder_report(
+    config: &PathConfig,
+    file_folder_queue: &FileSystemStack,
+    metadata: &DeletionMetaData,
+    request: &ScanRequest,
+) -> FolderReport {
+    let mut report = FolderReport::new(config, metadata, file_folder_queue);
+    if let TextOverviewType::Deletion = request.overview_type {
+        if request.auto_approve {
+            let mut summary = delete_files_scheduled_for_deletion(file_folder_queue);
+            prune_matched_directories(config, &mut summary);
+            report.set_deletion(&summary);
+        }
+    }
+    report
 }
 
-fn get_path_config_from_key(
-    user_key: &str,
-    relative_path: bool,
+/// Resolves the keys, paths and tags given on the command line into a single list of
+/// folders, in the order they were given, with each directory appearing only once.
+fn select_path_configs(
+    user_keys: &[String],
+    tags: &[String],
+    config_files: &ConfigFiles,
 ) -> Result<Vec<PathConfig>, CLIError> {
-    let config_path = get_user_config_path(CONFIG_FILE_NAME)?;
+    // Paths can be cleaned without a config file, so it's only an error to be
+    // missing one if a key or tag needs it. Any other problem with it is reported.
+    let all_configs =
+        match fetch_layered_configs(&config_files.layers, &config_files.user_path, None) {
+            Err(error @ ConfigError::ConfigNotFound(_)) => Err(error),
+            Err(error @ ConfigError::ReadError(_)) if config_files.layers.is_empty() => Err(error),
+            result => Ok(result?),
+        };
+    let configured = all_configs.as_deref().unwrap_or_default();
+    let mut selected: Vec<PathConfig> = Vec::new();
 
-    // TODO: Better messaging here?
-    // Better error handling w/ unwrap
-    match fetch_cli_configs(&config_path, Some(user_key)) {
-        Ok(config_group) => Ok(config_group),
-        Err(_) if validate_file_path(user_key) => Ok(vec![PathConfig::new(
-            PathBuf::from(user_key),
-            // Dictates how the path is displayed to the user
-            relative_path,
-        )]),
-        Err(_) => Err(CLIError::PathOrConfigError(user_key.to_string())),
+    for user_key in user_keys {
+        let matched = configs_in_matching_groups(configured, user_key);
+        if !matched.is_empty() {
+            selected.extend(expand_and_warn(matched));
+        } else if validate_file_path(user_key) {
+            // Paths are canonicalised, so folders are reported the same way
+            // however they were typed.
+            selected.push(PathConfig::new(PathBuf::from(user_key), false));
+        } else {
+            return Err(CLIError::PathOrConfigError(user_key.to_string()));
+        }
     }
+
+    if !tags.is_empty() {
+        let configured = all_configs?;
+        for tag in tags {
+            let tagged = configs_with_tag(&configured, tag);
+            if tagged.is_empty() {
+                return Err(CLIError::TagNotFound(tag.to_string()));
+            }
+            selected.extend(expand_and_warn(tagged));
+        }
+    }
+
+    Ok(dedupe_and_warn(selected))
+}
+
+fn warn_unexpanded(folder: &UnexpandedFolder) {
+    let group = folder.config.group.as_deref().unwrap_or_default();
+    eprintln!(
+        "Warning: Skipping {:?} in group '{}'. {}",
+        folder.config.directory, group, folder.error
+    );
+}
+
+// Expands the folders that were picked, leaving out any that can't be expanded so
+// the rest can still be used.
+fn expand_and_warn(configs: Vec<PathConfig>) -> Vec<PathConfig> {
+    let (configs, unexpanded) = expand_folders(configs);
+    unexpanded.iter().for_each(warn_unexpanded);
+    configs
+}
+
+// The same folder can be picked up more than once, e.g. from two groups. Only the
+// first is scanned, so say so if the others would have been cleaned differently.
+fn dedupe_and_warn(configs: Vec<PathConfig>) -> Vec<PathConfig> {
+    let (configs, duplicates) = dedupe_directories(configs);
+    for duplicate in duplicates {
+        let describe = |group: Option<String>| match group {
+            Some(group) => format!("group '{}'", group),
+            None => "the command line".to_string(),
+        };
+        eprintln!(
+            "Warning: {:?} is selected with different filters from {} and {}. Only the \
+            filters from {} are used.",
+            duplicate.directory,
+            describe(duplicate.kept_group.clone()),
+            describe(duplicate.dropped_group),
+            describe(duplicate.kept_group),
+        );
+    }
+    configs
+}
+
+// Every folder in every group of the config file, with the groups in name order.
+fn get_all_path_configs(config_files: &ConfigFiles) -> Result<Vec<PathConfig>, CLIError> {
+    Ok(fetch_layered_configs(
+        &config_files.layers,
+        &config_files.user_path,
+        None,
+    )?)
+}
+
+// `-r` and `-i` only ever switch settings on, so a group that already scans subfolders
+// or hidden files keeps doing so. Returns the settings the flags changed.
+fn apply_cli_overrides(
+    config: &mut PathConfig,
+    recursive: bool,
+    include_hidden: bool,
+) -> Vec<String> {
+    let mut overrides = Vec::new();
+    if recursive && !config.recursive {
+        config.recursive = true;
+        overrides.push("recursive (-r)".to_string());
+    }
+    if include_hidden && !config.delete_hidden {
+        config.delete_hidden = true;
+        overrides.push("delete hidden files (-i)".to_string());
+    }
+    overrides
 }
 
 fn update_configs_with_cli_args(
     configs: Vec<PathConfig>,
     cleaner_args: &DirectoryArgs,
 ) -> Vec<PathConfig> {
+    let preset = cleaner_args.preset.as_deref().and_then(find_preset);
     configs
         .into_iter()
         .map(|mut config| {
-            config.recursive = cleaner_args.recursive;
-            config.delete_hidden = cleaner_args.include_hidden;
+            apply_cli_overrides(
+                &mut config,
+                cleaner_args.recursive,
+                cleaner_args.include_hidden,
+            );
+            if let Some(preset) = preset {
+                preset.apply_to(&mut config);
+            }
+            config.track_kept_files = cleaner_args.tree_args.show_kept;
 
             config
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs::parsing::{parse_config_from_str, ConfigFormat};
+    use clap::Parser;
+
+    fn resolve(content: &str, group: &str, flags: &[&str]) -> PathConfig {
+        let groups = parse_config_from_str(content, ConfigFormat::Toml)
+            .unwrap()
+            .resolve_groups()
+            .unwrap()
+            .unwrap();
+        let args = DirectoryArgs::parse_from(["size", group].iter().chain(flags));
+        let mut configs = update_configs_with_cli_args(groups[group].clone(), &args);
+        configs.remove(0)
+    }
+
+    #[test]
+    fn test_flags_only_switch_config_settings_on() {
+        let content = "[[logs]]\ndirectory = \"/tmp\"\ndelete_hidden = true\nrecursive = true\n";
+        let config = resolve(content, "logs", &[]);
+        assert!(config.delete_hidden);
+        assert!(config.recursive);
+
+        let content = "[[logs]]\ndirectory = \"/tmp\"\n";
+        let config = resolve(content, "logs", &["-r", "-i"]);
+        assert!(config.delete_hidden);
+        assert!(config.recursive);
+    }
+
+    #[test]
+    fn test_profiles_resolve_like_presets() {
+        let content = "[[cruft]]\ndirectory = \"/tmp\"\nprofile = \"os-cruft\"\n";
+        let from_profile = resolve(content, "cruft", &[]);
+        let content = "[[cruft]]\ndirectory = \"/tmp\"\n";
+        let from_preset = resolve(content, "cruft", &["--preset", "os-cruft"]);
+
+        assert!(from_profile.delete_hidden);
+        assert!(from_profile.recursive);
+        assert!(from_profile.has_same_filters(&from_preset));
+    }
+}
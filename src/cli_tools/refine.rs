@@ -0,0 +1,191 @@
+use inquire::validator::Validation;
+use inquire::{CustomType, InquireError, Select, Text};
+use std::fmt;
+
+use crate::configs::config::PathConfig;
+
+/// A single change to the filters of the folder being cleaned.
+pub enum RefineAction {
+    AddDelete,
+    RemoveDelete,
+    AddKeep,
+    RemoveKeep,
+    ToggleHidden,
+    SetAge,
+    Done,
+}
+
+impl fmt::Display for RefineAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            RefineAction::AddDelete => "Add an extension to delete",
+            RefineAction::RemoveDelete => "Remove an extension to delete",
+            RefineAction::AddKeep => "Add an extension to keep",
+            RefineAction::RemoveKeep => "Remove an extension to keep",
+            RefineAction::ToggleHidden => "Toggle deleting hidden files",
+            RefineAction::SetAge => "Set the minimum age of deleted files",
+            RefineAction::Done => "Done",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Extensions are compared without their leading dot and regardless of case.
+fn normalise_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// Adds an extension to a filter list, creating the list if need be.
+/// Returns false if the extension was empty or already listed.
+pub fn add_extension(extensions: &mut Option<Vec<String>>, extension: &str) -> bool {
+    let extension = normalise_extension(extension);
+    if extension.is_empty() {
+        return false;
+    }
+    let extensions = extensions.get_or_insert_with(Vec::new);
+    if extensions
+        .iter()
+        .any(|listed| normalise_extension(listed) == extension)
+    {
+        return false;
+    }
+    extensions.push(extension);
+    true
+}
+
+/// Removes an extension from a filter list, dropping the list once it's empty.
+/// Returns false if the extension wasn't listed.
+pub fn remove_extension(extensions: &mut Option<Vec<String>>, extension: &str) -> bool {
+    let extension = normalise_extension(extension);
+    let Some(list) = extensions.as_mut() else {
+        return false;
+    };
+    let length = list.len();
+    list.retain(|listed| normalise_extension(listed) != extension);
+    let removed = list.len() != length;
+    if list.is_empty() {
+        *extensions = None;
+    }
+    removed
+}
+
+fn prompt_extension_to_add(message: &str) -> Result<String, InquireError> {
+    Text::new(message)
+        .with_help_message("e.g. log or .tmp")
+        .with_validator(|input: &str| match normalise_extension(input).is_empty() {
+            true => Ok(Validation::Invalid("Enter an extension".into())),
+            false => Ok(Validation::Valid),
+        })
+        .prompt()
+}
+
+fn prompt_extension_to_remove(
+    message: &str,
+    extensions: &Option<Vec<String>>,
+) -> Result<Option<String>, InquireError> {
+    match extensions {
+        Some(extensions) if !extensions.is_empty() => {
+            Select::new(message, extensions.clone()).prompt().map(Some)
+        }
+        _ => {
+            println!("There are no extensions to remove.");
+            Ok(None)
+        }
+    }
+}
+
+fn prompt_age_cutoff(current: Option<u64>) -> Result<Option<u64>, InquireError> {
+    let mut prompt = CustomType::<u64>::new("Only delete files older than how many days?")
+        .with_help_message("0 deletes files of any age")
+        .with_error_message("Enter a whole number of days");
+    if let Some(days) = current {
+        prompt = prompt.with_default(days);
+    }
+    prompt
+        .prompt()
+        .map(|days| Some(days).filter(|days| *days > 0))
+}
+
+/// Asks the user which filter to change and applies it to `config`.
+///
+/// # Returns
+///
+/// Whether any of the filters changed, or an error if a prompt couldn't be shown.
+pub fn refine_filters(config: &mut PathConfig) -> Result<bool, InquireError> {
+    let actions = vec![
+        RefineAction::AddDelete,
+        RefineAction::RemoveDelete,
+        RefineAction::AddKeep,
+        RefineAction::RemoveKeep,
+        RefineAction::ToggleHidden,
+        RefineAction::SetAge,
+        RefineAction::Done,
+    ];
+
+    let changed = match Select::new("Which filter would you like to change?", actions).prompt()? {
+        RefineAction::AddDelete => {
+            let extension = prompt_extension_to_add("Extension to delete:")?;
+            add_extension(&mut config.extensions_to_delete, &extension)
+        }
+        RefineAction::RemoveDelete => {
+            let removed =
+                prompt_extension_to_remove("Stop deleting:", &config.extensions_to_delete)?;
+            let changed = removed.is_some_and(|extension| {
+                remove_extension(&mut config.extensions_to_delete, &extension)
+            });
+            if changed && config.extensions_to_delete.is_none() {
+                println!(
+                    "No extensions are listed for deletion, so any extension will be deleted."
+                );
+            }
+            changed
+        }
+        RefineAction::AddKeep => {
+            let extension = prompt_extension_to_add("Extension to keep:")?;
+            add_extension(&mut config.extensions_to_keep, &extension)
+        }
+        RefineAction::RemoveKeep => {
+            let removed = prompt_extension_to_remove("Stop keeping:", &config.extensions_to_keep)?;
+            removed.is_some_and(|extension| {
+                remove_extension(&mut config.extensions_to_keep, &extension)
+            })
+        }
+        RefineAction::ToggleHidden => {
+            config.delete_hidden = !config.delete_hidden;
+            true
+        }
+        RefineAction::SetAge => {
+            let days = prompt_age_cutoff(config.older_than_days)?;
+            let changed = days != config.older_than_days;
+            config.older_than_days = days;
+            changed
+        }
+        RefineAction::Done => false,
+    };
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_extension_normalises_and_skips_duplicates() {
+        let mut extensions = None;
+        assert!(add_extension(&mut extensions, ".LOG"));
+        assert!(!add_extension(&mut extensions, "log"));
+        assert!(!add_extension(&mut extensions, " . "));
+        assert!(add_extension(&mut extensions, "tmp"));
+        assert_eq!(extensions, Some(vec!["log".to_string(), "tmp".to_string()]));
+    }
+
+    #[test]
+    fn test_remove_extension_drops_empty_lists() {
+        let mut extensions = Some(vec!["log".to_string(), "tmp".to_string()]);
+        assert!(remove_extension(&mut extensions, ".TMP"));
+        assert!(!remove_extension(&mut extensions, "rs"));
+        assert!(remove_extension(&mut extensions, "log"));
+        assert_eq!(extensions, None);
+        assert!(!remove_extension(&mut extensions, "log"));
+    }
+}
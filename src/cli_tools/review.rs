@@ -0,0 +1,256 @@
+use inquire::list_option::ListOption;
+use inquire::{InquireError, MultiSelect};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::cleaning::track_files_for_deletion::DeletionMetaData;
+use crate::logging::folder_tree_helpers::DirTreeLeaf;
+use crate::logging::process_directory_tree::FileSystemStack;
+use crate::logging::style::current_style;
+use crate::utils::{escape_path, format_size};
+
+// How many options are shown at once; the rest are reached by scrolling.
+const REVIEW_PAGE_SIZE: usize = 20;
+
+// A single line in the review prompt: either a folder heading, which stands
+// for every candidate directly inside it, or an individual candidate file.
+enum ReviewOption {
+    Folder {
+        path: PathBuf,
+        label: String,
+        file_count: usize,
+        size: u64,
+    },
+    File {
+        leaf: DirTreeLeaf,
+        label: String,
+    },
+}
+
+impl fmt::Display for ReviewOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReviewOption::Folder {
+                label,
+                file_count,
+                size,
+                ..
+            } => {
+                let folder = current_style().symbol("📁 ", "");
+                write!(
+                    f,
+                    "{}{} ({} files, {})",
+                    folder,
+                    label,
+                    file_count,
+                    format_size(*size)
+                )
+            }
+            ReviewOption::File { leaf, label } => {
+                write!(f, "    {} ({})", label, format_size(leaf.size))
+            }
+        }
+    }
+}
+
+fn relative_label(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => escape_path(relative),
+        Err(_) => escape_path(path),
+    }
+}
+
+// Lists the candidates grouped under the directory that holds them.
+fn build_review_options(root: &Path, candidates: &FileSystemStack) -> Vec<ReviewOption> {
+    let mut folders: BTreeMap<PathBuf, Vec<&DirTreeLeaf>> = BTreeMap::new();
+    for leaf in candidates {
+        let parent = leaf.key.parent().unwrap_or(root).to_path_buf();
+        folders.entry(parent).or_default().push(leaf);
+    }
+
+    let mut options = Vec::new();
+    for (path, files) in folders {
+        options.push(ReviewOption::Folder {
+            label: relative_label(root, &path),
+            file_count: files.len(),
+            size: files.iter().map(|leaf| leaf.size).sum(),
+            path,
+        });
+        for leaf in files {
+            let label = leaf
+                .key
+                .file_name()
+                .map(|name| escape_path(Path::new(name)))
+                .unwrap_or_else(|| escape_path(&leaf.key));
+            options.push(ReviewOption::File {
+                leaf: leaf.clone(),
+                label,
+            });
+        }
+    }
+    options
+}
+
+// A file is confirmed if both it and the folder holding it are still ticked.
+fn confirmed_files(options: &[ReviewOption], ticked: &HashSet<usize>) -> FileSystemStack {
+    let mut confirmed = FileSystemStack::new();
+    let mut folder_ticked = false;
+
+    for (index, option) in options.iter().enumerate() {
+        match option {
+            ReviewOption::Folder { .. } => folder_ticked = ticked.contains(&index),
+            ReviewOption::File { leaf, .. } => {
+                if folder_ticked && ticked.contains(&index) {
+                    confirmed.push_back(leaf.clone());
+                }
+            }
+        }
+    }
+    confirmed
+}
+
+// Ticks every file in the current selection, along with the folders holding them.
+fn preselected_options(options: &[ReviewOption], selected: &FileSystemStack) -> Vec<usize> {
+    let selected_paths: HashSet<&Path> = selected.iter().map(|leaf| leaf.key.as_path()).collect();
+
+    options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| match option {
+            ReviewOption::Folder { path, .. } => selected_paths
+                .iter()
+                .any(|selected| selected.parent() == Some(path.as_path())),
+            ReviewOption::File { leaf, .. } => selected_paths.contains(leaf.key.as_path()),
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Lets the user untick individual files, or whole folders, from the deletion candidates.
+///
+/// # Arguments
+///
+/// * `root` - The scanned folder, used to shorten the paths shown.
+/// * `candidates` - Every file scheduled for deletion.
+/// * `selected` - The files currently selected, which start off ticked.
+///
+/// # Returns
+///
+/// The files the user confirmed, or an error if the prompt couldn't be shown.
+pub fn review_candidates(
+    root: &Path,
+    candidates: &FileSystemStack,
+    selected: &FileSystemStack,
+) -> Result<FileSystemStack, InquireError> {
+    let options = build_review_options(root, candidates);
+    let preselected = preselected_options(&options, selected);
+    let option_count = options.len();
+
+    let ticked = MultiSelect::new("Select the files to delete:", options.iter().collect())
+        .with_default(&preselected)
+        .with_page_size(REVIEW_PAGE_SIZE)
+        .with_help_message("space to toggle, → all, ← none, type to filter, enter to confirm")
+        .with_formatter(&|ticked: &[ListOption<&&ReviewOption>]| {
+            format!("{} of {} entries selected", ticked.len(), option_count)
+        })
+        .raw_prompt()?;
+
+    let ticked_indices: HashSet<usize> = ticked.iter().map(|option| option.index).collect();
+    Ok(confirmed_files(&options, &ticked_indices))
+}
+
+/// Recalculates the deletion totals for the files that are still selected.
+pub fn summarise_selection(
+    deletion_metadata: &DeletionMetaData,
+    selected: &FileSystemStack,
+) -> DeletionMetaData {
+    let folders: HashSet<&Path> = selected
+        .iter()
+        .filter_map(|leaf| leaf.key.parent())
+        .collect();
+
+    DeletionMetaData {
+        deletion_size: selected.iter().map(|leaf| leaf.size).sum(),
+        file_count: selected.len(),
+        dir_count: folders.len(),
+        ..deletion_metadata.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(path: &str, size: u64) -> DirTreeLeaf {
+        DirTreeLeaf {
+            key: PathBuf::from(path),
+            size,
+            ..Default::default()
+        }
+    }
+
+    fn build_candidates() -> FileSystemStack {
+        [
+            candidate("root/a.log", 10),
+            candidate("root/logs/b.log", 20),
+            candidate("root/logs/c.log", 30),
+            candidate("root/tmp/d.tmp", 40),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_review_options_grouped_by_folder() {
+        let options = build_review_options(Path::new("root"), &build_candidates());
+        let labels: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+
+        assert_eq!(
+            labels,
+            vec![
+                "📁 . (1 files, 10.00 B)",
+                "    a.log (10.00 B)",
+                "📁 logs (2 files, 50.00 B)",
+                "    b.log (20.00 B)",
+                "    c.log (30.00 B)",
+                "📁 tmp (1 files, 40.00 B)",
+                "    d.tmp (40.00 B)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unticked_files_and_folders_are_dropped() {
+        let candidates = build_candidates();
+        let options = build_review_options(Path::new("root"), &candidates);
+
+        // Everything ticked bar c.log and the tmp folder (though d.tmp is still ticked).
+        let ticked: HashSet<usize> = [0, 1, 2, 3, 6].into_iter().collect();
+        let confirmed = confirmed_files(&options, &ticked);
+        let paths: Vec<&Path> = confirmed.iter().map(|leaf| leaf.key.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("root/a.log"), Path::new("root/logs/b.log")]
+        );
+
+        assert_eq!(preselected_options(&options, &confirmed), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_summarise_selection_updates_totals() {
+        let mut metadata = DeletionMetaData::from_root_folder(Path::new("missing"));
+        metadata.folder_size = 1000;
+        metadata.deletion_size = 100;
+        metadata.file_count = 4;
+
+        let selected: FileSystemStack = build_candidates().into_iter().skip(1).take(2).collect();
+        let summary = summarise_selection(&metadata, &selected);
+
+        assert_eq!(summary.folder_size, 1000);
+        assert_eq!(summary.deletion_size, 50);
+        assert_eq!(summary.file_count, 2);
+        assert_eq!(summary.dir_count, 1);
+    }
+}
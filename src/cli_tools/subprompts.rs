@@ -1,69 +1,260 @@
-use inquire::{InquireError, Select};
+use inquire::{InquireError, Select, Text};
 use std::fmt;
+use std::fs::canonicalize;
+use std::path::Path;
 use std::process;
 
-use crate::cleaning::delete_files_scheduled_for_deletion;
+use super::refine::refine_filters;
+use super::review::{review_candidates, summarise_selection};
+use crate::cleaning::track_files_for_deletion::{reclassify_tracked_files, DeletionMetaData};
+use crate::cleaning::{
+    delete_files_scheduled_for_deletion, prune_matched_directories, scheduled_files,
+};
+use crate::configs::config::PathConfig;
+use crate::configs::editing::save_to_group;
+use crate::logging::folder_tree_helpers::DirTreeOptions;
 use crate::logging::process_directory_tree::FileSystemStack;
-use crate::logging::{print_directory_tree, TextOverviewType};
+use crate::logging::style::current_style;
+use crate::logging::{print_directory_tree, print_largest_entries, TextOverviewType};
+
+// The number of files and directories listed by the "largest" option.
+const LARGEST_ENTRIES_LIMIT: usize = 10;
+
+/// Everything the prompt commands need to know about the folder being reported on.
+///
+/// The config, scan and totals are owned so that refining the filters can
+/// update them in place, without going back to the disk.
+pub struct PromptContext<'a> {
+    pub config: PathConfig,
+    pub directory_stack: FileSystemStack,
+    pub deletion_metadata: DeletionMetaData,
+    pub overview_type: &'a TextOverviewType,
+    pub tree_options: &'a DirTreeOptions,
+    // Where the filters are saved to.
+    pub config_path: &'a Path,
+    // The files that will be deleted, which starts off as every candidate.
+    pub selected: FileSystemStack,
+}
+
+impl<'a> PromptContext<'a> {
+    pub fn new(
+        config: PathConfig,
+        directory_stack: FileSystemStack,
+        deletion_metadata: DeletionMetaData,
+        overview_type: &'a TextOverviewType,
+        tree_options: &'a DirTreeOptions,
+        config_path: &'a Path,
+    ) -> PromptContext<'a> {
+        let selected = scheduled_files(&directory_stack);
+        PromptContext {
+            config,
+            directory_stack,
+            deletion_metadata,
+            overview_type,
+            tree_options,
+            config_path,
+            selected,
+        }
+    }
+}
 
 pub enum PromptArg {
     Delete,
+    Review,
+    Refine,
+    Save,
     Exit,
     Tree,
+    Largest,
 }
 
 impl fmt::Display for PromptArg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.as_str())
+        let (label, emoji) = self.label_and_emoji();
+        match current_style().symbol(emoji, "") {
+            "" => write!(f, "{}", label),
+            emoji => write!(f, "{} {}", label, emoji),
+        }
     }
 }
 
 impl PromptArg {
-    fn as_str(&self) -> &str {
+    fn label_and_emoji(&self) -> (&str, &str) {
         match self {
-            PromptArg::Delete => "Delete 🗑️",
-            PromptArg::Exit => "Exit 🚪",
-            PromptArg::Tree => "Print directory tree 🌲",
+            PromptArg::Delete => ("Delete", "🗑️"),
+            PromptArg::Review => ("Review and select files", "✅"),
+            PromptArg::Refine => ("Refine filters", "🔍"),
+            PromptArg::Save => ("Save settings to a config group", "💾"),
+            PromptArg::Exit => ("Exit", "🚪"),
+            PromptArg::Tree => ("Print directory tree", "🌲"),
+            PromptArg::Largest => ("Show largest files and directories", "🏆"),
         }
     }
 
-    pub fn process_command(&self, directory_stack: FileSystemStack) {
+    /// Whether the user should be prompted again once the command has run. Exiting
+    /// ends the whole run, whereas deleting moves on to the next folder.
+    pub fn returns_to_prompt(&self) -> bool {
+        matches!(
+            self,
+            PromptArg::Review
+                | PromptArg::Refine
+                | PromptArg::Save
+                | PromptArg::Tree
+                | PromptArg::Largest
+        )
+    }
+
+    pub fn process_command(&self, context: &mut PromptContext) {
         match self {
             PromptArg::Delete => {
-                match delete_files_scheduled_for_deletion(directory_stack) {
-                    Ok(_) => {
-                        println!("All files were successfully deleted.");
-                    }
-                    Err(e) => {
-                        eprintln!("{}", e);
-                    }
+                if context.selected.is_empty() {
+                    println!("No files were selected for deletion.");
+                    return;
+                }
+                let mut summary = delete_files_scheduled_for_deletion(&context.selected);
+                prune_matched_directories(&context.config, &mut summary);
+                match summary.is_complete() {
+                    true => println!("All files were successfully deleted."),
+                    false => eprintln!("Warning: not all files could be deleted"),
+                }
+                if !summary.removed_dirs.is_empty() {
+                    println!("Removed {} emptied folders.", summary.removed_dirs.len());
                 }
-                process::exit(0);
             }
             PromptArg::Exit => {
                 println!("Exiting the program");
                 process::exit(0);
             }
+            PromptArg::Review => {
+                review_selection(context);
+            }
+            PromptArg::Refine => {
+                refine_selection(context);
+            }
+            PromptArg::Save => {
+                save_settings(&context.config, context.config_path);
+            }
             PromptArg::Tree => {
-                print_directory_tree(directory_stack);
+                print_directory_tree(&context.directory_stack, context.tree_options);
+            }
+            PromptArg::Largest => {
+                print_largest_entries(&context.directory_stack, LARGEST_ENTRIES_LIMIT);
             }
         }
     }
 }
 
+// Lets the user narrow down the files to delete, then shows the overview
+// again with totals covering just the selected files.
+fn review_selection(context: &mut PromptContext) {
+    let candidates = scheduled_files(&context.directory_stack);
+    match review_candidates(&context.config.directory, &candidates, &context.selected) {
+        Ok(selected) => {
+            let summary = summarise_selection(&context.deletion_metadata, &selected);
+            println!(
+                "{}",
+                context
+                    .overview_type
+                    .generate_text(&context.config, &summary)
+            );
+            context.selected = selected;
+        }
+        Err(e) => eprintln!("Error encountered during review: {}", e),
+    }
+}
+
+// Lets the user change one of the filters, then classifies the scanned files
+// again and shows the updated overview. Any earlier review is discarded, as
+// the candidates it was based on may have changed.
+fn refine_selection(context: &mut PromptContext) {
+    match refine_filters(&mut context.config) {
+        Ok(true) => {
+            reclassify_tracked_files(
+                &mut context.directory_stack,
+                &mut context.deletion_metadata,
+                &context.config,
+            );
+            context.selected = scheduled_files(&context.directory_stack);
+            println!(
+                "{}",
+                context
+                    .overview_type
+                    .generate_text(&context.config, &context.deletion_metadata)
+            );
+        }
+        Ok(false) => {}
+        Err(e) => eprintln!("Error encountered while refining the filters: {}", e),
+    }
+}
+
+// Saves the current filters as an entry in a config group, so the same
+// clean can be run again by name.
+fn save_settings(config: &PathConfig, config_path: &Path) {
+    let default_group = config
+        .group
+        .clone()
+        .or_else(|| {
+            config
+                .directory
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+
+    let group = match Text::new("Save to which config group?")
+        .with_default(&default_group)
+        .prompt()
+    {
+        Ok(group) if !group.trim().is_empty() => group.trim().to_string(),
+        Ok(_) => {
+            println!("No group name was given, so nothing was saved.");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Error encountered while saving: {}", e);
+            return;
+        }
+    };
+
+    // Config files are read from anywhere, so relative paths are stored in full.
+    let mut saved = config.clone();
+    if let Ok(directory) = canonicalize(&config.directory) {
+        saved.directory = directory;
+    }
+    match save_to_group(config_path, &group, &saved) {
+        Ok(()) => println!(
+            "Saved the settings to the '{}' group in {:?}.",
+            group, config_path
+        ),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
 pub fn prompt_user_decision(text_overview_type: &TextOverviewType) -> PromptArg {
-    let deletion_args: Vec<PromptArg> = vec![PromptArg::Delete, PromptArg::Exit, PromptArg::Tree];
-    let size_args: Vec<PromptArg> = vec![PromptArg::Exit, PromptArg::Tree];
+    let deletion_args: Vec<PromptArg> = vec![
+        PromptArg::Delete,
+        PromptArg::Review,
+        PromptArg::Refine,
+        PromptArg::Save,
+        PromptArg::Exit,
+        PromptArg::Tree,
+        PromptArg::Largest,
+    ];
+    let size_args: Vec<PromptArg> = vec![PromptArg::Exit, PromptArg::Tree, PromptArg::Largest];
 
-    let deletion_prompt = "👉 Would you like to proceed with the deletion?";
-    let size_prompt = "👉 Would you like to see the directory tree representation?";
+    let style = current_style();
+    let deletion_prompt = style.prefixed("👉", "Would you like to proceed with the deletion?");
+    let size_prompt = style.prefixed(
+        "👉",
+        "Would you like to see the directory tree representation?",
+    );
 
     let (options, prompt) = match text_overview_type {
         TextOverviewType::Deletion => (deletion_args, deletion_prompt),
         TextOverviewType::Size => (size_args, size_prompt),
     };
 
-    let argument: Result<PromptArg, InquireError> = Select::new(prompt, options).prompt();
+    let argument: Result<PromptArg, InquireError> = Select::new(&prompt, options).prompt();
 
     match argument {
         // Return the option selected by the user
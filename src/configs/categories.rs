@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+/// Category name -> extensions, as written in the `[categories]` table of the config.
+pub type CategoryOverrides = HashMap<String, Vec<String>>;
+
+pub const UNCATEGORISED: &str = "other";
+
+const DEFAULT_CATEGORIES: [(&str, &[&str]); 7] = [
+    (
+        "images",
+        &[
+            "png", "jpg", "jpeg", "gif", "bmp", "svg", "webp", "heic", "tif", "tiff", "ico", "raw",
+        ],
+    ),
+    (
+        "video",
+        &["mp4", "mkv", "mov", "avi", "webm", "m4v", "wmv", "flv"],
+    ),
+    ("audio", &["mp3", "wav", "flac", "aac", "ogg", "m4a"]),
+    (
+        "archives",
+        &[
+            "zip", "tar", "gz", "tgz", "bz2", "xz", "7z", "rar", "zst", "dmg", "iso",
+        ],
+    ),
+    (
+        "code",
+        &[
+            "rs", "py", "js", "ts", "jsx", "tsx", "go", "c", "h", "cpp", "hpp", "java", "kt", "rb",
+            "php", "sh", "swift", "cs", "html", "css", "json", "toml", "yaml", "yml",
+        ],
+    ),
+    (
+        "documents",
+        &[
+            "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "txt", "md", "rtf",
+            "csv", "epub",
+        ],
+    ),
+    ("logs", &["log"]),
+];
+
+/// Maps file extensions onto broad categories, such as images or archives.
+pub struct CategoryMap {
+    extension_to_category: HashMap<String, String>,
+}
+
+impl Default for CategoryMap {
+    fn default() -> Self {
+        let extension_to_category = DEFAULT_CATEGORIES
+            .iter()
+            .flat_map(|(category, extensions)| {
+                extensions
+                    .iter()
+                    .map(move |ext| (ext.to_string(), category.to_string()))
+            })
+            .collect();
+
+        CategoryMap {
+            extension_to_category,
+        }
+    }
+}
+
+impl CategoryMap {
+    /// Builds the default map, then applies any overrides from the config.
+    ///
+    /// An overridden category replaces the default extension list for that category
+    /// entirely. Extensions claimed by an override are removed from whichever
+    /// category they previously belonged to.
+    pub fn with_overrides(overrides: Option<&CategoryOverrides>) -> Self {
+        let mut category_map = CategoryMap::default();
+
+        for (category, extensions) in overrides.into_iter().flatten() {
+            category_map
+                .extension_to_category
+                .retain(|_, existing| existing != category);
+            for ext in extensions {
+                let ext = ext.trim_start_matches('.').to_lowercase();
+                category_map
+                    .extension_to_category
+                    .insert(ext, category.to_string());
+            }
+        }
+
+        category_map
+    }
+
+    pub fn category_for(&self, extension: &str) -> &str {
+        self.extension_to_category
+            .get(&extension.to_lowercase())
+            .map(|category| category.as_str())
+            .unwrap_or(UNCATEGORISED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_categories() {
+        let categories = CategoryMap::default();
+        assert_eq!(categories.category_for("PNG"), "images");
+        assert_eq!(categories.category_for("zip"), "archives");
+        assert_eq!(categories.category_for("unknown"), UNCATEGORISED);
+        assert_eq!(categories.category_for(""), UNCATEGORISED);
+    }
+
+    #[test]
+    fn test_overrides_replace_and_extend_categories() {
+        let overrides: CategoryOverrides = HashMap::from([
+            ("images".to_string(), vec!["psd".to_string()]),
+            (
+                "notebooks".to_string(),
+                vec![".ipynb".to_string(), "json".to_string()],
+            ),
+        ]);
+        let categories = CategoryMap::with_overrides(Some(&overrides));
+
+        assert_eq!(categories.category_for("psd"), "images");
+        assert_eq!(categories.category_for("png"), UNCATEGORISED);
+        assert_eq!(categories.category_for("ipynb"), "notebooks");
+        assert_eq!(categories.category_for("json"), "notebooks");
+        assert_eq!(categories.category_for("rs"), "code");
+    }
+}
@@ -1,10 +1,18 @@
+use super::categories::CategoryOverrides;
+use super::errors::ConfigError;
+use super::presets::{find_preset, Preset};
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{MapAccess, SeqAccess, Visitor};
 use serde::{self, Deserialize, Deserializer};
 use serde_derive::Deserialize;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::canonicalize;
 use std::path::PathBuf;
 
-pub type PathConfigMap = HashMap<String, Vec<PathConfig>>;
+// Groups are kept in name order, so they are always listed the same way.
+pub type PathConfigMap = BTreeMap<String, Vec<PathConfig>>;
+pub type GroupMap = BTreeMap<String, GroupSpec>;
 
 // TODO: Improve deserialisation of extensions
 // Test - extensions_to_del = ["tmp", ".log", "..rs"]
@@ -15,7 +23,7 @@ where
     let optional_extensions: Option<Vec<String>> = Option::deserialize(deserializer)?;
 
     match optional_extensions {
-        None => return Ok(None),
+        None => Ok(None),
         Some(vec) => {
             let cleaned_vec: Vec<String> = vec
                 .into_iter()
@@ -27,29 +35,347 @@ where
 }
 
 // Config struct holds to data from the `[config]` section.
-#[derive(Deserialize, Debug)]
+#[derive(Debug)]
 pub struct Config {
+    // Optional `[categories]` table, overriding the default extension categories
+    pub categories: Option<CategoryOverrides>,
+    // Optional `[defaults]` table, shared by every folder in every group
+    pub defaults: Option<FolderSettings>,
+    // Optional `[profiles.<name>]` tables, used by entries with `profile = "<name>"`
+    pub profiles: Option<BTreeMap<String, FolderSettings>>,
     // The user needn't setup any subgroups if they don't wish to
-    #[serde(flatten)]
-    pub subgroups: Option<PathConfigMap>,
+    pub subgroups: Option<GroupMap>,
 }
 
-#[derive(Deserialize, Debug)]
+// Every top-level key that isn't one of the config's own tables is a group. Groups are
+// read as they're found rather than through a flattened map, which would buffer them
+// and lose the position of any error inside them.
+struct ConfigVisitor;
+
+impl<'de> Visitor<'de> for ConfigVisitor {
+    type Value = Config;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a table of groups")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Config, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut config = Config {
+            categories: None,
+            defaults: None,
+            profiles: None,
+            subgroups: None,
+        };
+        let mut subgroups = GroupMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "categories" => config.categories = map.next_value()?,
+                "defaults" => config.defaults = map.next_value()?,
+                "profiles" => config.profiles = map.next_value()?,
+                _ => {
+                    subgroups.insert(key, map.next_value()?);
+                }
+            }
+        }
+        config.subgroups = Some(subgroups).filter(|subgroups| !subgroups.is_empty());
+        Ok(config)
+    }
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(ConfigVisitor)
+    }
+}
+
+// YAML and JSON configs often write an unset setting as `null`, which leaves it empty.
+fn deserialise_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Settings shared between folders through `[defaults]`, a group's own defaults or a
+/// profile. Anything left out falls through to the level below.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct FolderSettings {
+    #[serde(default, deserialize_with = "deserialise_extensions")]
+    pub extensions_to_delete: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialise_extensions")]
+    pub extensions_to_keep: Option<Vec<String>>,
+    pub names_to_delete: Option<Vec<String>>,
+    pub recursive: Option<bool>,
+    pub delete_hidden: Option<bool>,
+    pub older_than_days: Option<u64>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl FolderSettings {
+    /// Layers `overlay` on top of these settings. Each setting `overlay` gives replaces
+    /// the one below it entirely, lists included.
+    pub fn merged_with(self, overlay: &FolderSettings) -> FolderSettings {
+        FolderSettings {
+            extensions_to_delete: overlay
+                .extensions_to_delete
+                .clone()
+                .or(self.extensions_to_delete),
+            extensions_to_keep: overlay
+                .extensions_to_keep
+                .clone()
+                .or(self.extensions_to_keep),
+            names_to_delete: overlay.names_to_delete.clone().or(self.names_to_delete),
+            recursive: overlay.recursive.or(self.recursive),
+            delete_hidden: overlay.delete_hidden.or(self.delete_hidden),
+            older_than_days: overlay.older_than_days.or(self.older_than_days),
+            tags: overlay.tags.clone().or(self.tags),
+        }
+    }
+}
+
+/// A single folder as it's written in the config, before any defaults are applied.
+#[derive(Deserialize, Debug, Clone)]
+// Unknown keys are rejected, so typos are reported rather than silently ignored.
+#[serde(deny_unknown_fields)]
+pub struct FolderEntry {
+    pub directory: PathBuf,
+    // The name of a `[profiles.<name>]` table to take settings from
+    pub profile: Option<String>,
+    #[serde(default, deserialize_with = "deserialise_extensions")]
+    pub extensions_to_delete: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialise_extensions")]
+    pub extensions_to_keep: Option<Vec<String>>,
+    // File or folder names to delete, which may be globs such as `*~`.
+    pub names_to_delete: Option<Vec<String>>,
+    pub recursive: Option<bool>,
+    pub delete_hidden: Option<bool>,
+    // Only files last modified more than this many days ago are deleted.
+    pub older_than_days: Option<u64>,
+    // Labels used to select folders across groups, e.g. `--tag nightly`.
+    pub tags: Option<Vec<String>>,
+}
+
+impl FolderEntry {
+    fn settings(&self) -> FolderSettings {
+        FolderSettings {
+            extensions_to_delete: self.extensions_to_delete.clone(),
+            extensions_to_keep: self.extensions_to_keep.clone(),
+            names_to_delete: self.names_to_delete.clone(),
+            recursive: self.recursive,
+            delete_hidden: self.delete_hidden,
+            older_than_days: self.older_than_days,
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+/// A group written as a table, so it can hold defaults for its folders:
+///
+/// ```toml
+/// [downloads.defaults]
+/// older_than_days = 30
+///
+/// [[downloads.folders]]
+/// directory = "~/Downloads"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GroupTable {
+    #[serde(default, deserialize_with = "deserialise_or_default")]
+    pub defaults: FolderSettings,
+    #[serde(default, deserialize_with = "deserialise_or_default")]
+    pub folders: Vec<FolderEntry>,
+}
+
+/// A config group, written either as a list of `[[group]]` entries or as a table.
+#[derive(Debug, Clone)]
+pub enum GroupSpec {
+    Folders(Vec<FolderEntry>),
+    Table(GroupTable),
+}
+
+// A group is read in whichever form it was written in, so errors come from that form
+// rather than a vague "didn't match any form".
+struct GroupSpecVisitor;
+
+impl<'de> Visitor<'de> for GroupSpecVisitor {
+    type Value = GroupSpec;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a group of [[group]] entries or a group table")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<GroupSpec, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Vec::deserialize(SeqAccessDeserializer::new(seq)).map(GroupSpec::Folders)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<GroupSpec, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        GroupTable::deserialize(MapAccessDeserializer::new(map)).map(GroupSpec::Table)
+    }
+}
+
+impl<'de> Deserialize<'de> for GroupSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(GroupSpecVisitor)
+    }
+}
+
+impl GroupSpec {
+    fn parts(&self) -> (Option<&FolderSettings>, &[FolderEntry]) {
+        match self {
+            GroupSpec::Folders(entries) => (None, entries),
+            GroupSpec::Table(table) => (Some(&table.defaults), &table.folders),
+        }
+    }
+
+    pub fn entries(&self) -> &[FolderEntry] {
+        self.parts().1
+    }
+
+    pub fn entries_mut(&mut self) -> &mut Vec<FolderEntry> {
+        match self {
+            GroupSpec::Folders(entries) => entries,
+            GroupSpec::Table(table) => &mut table.folders,
+        }
+    }
+}
+
+impl Config {
+    pub fn empty() -> Config {
+        Config {
+            categories: None,
+            defaults: None,
+            profiles: None,
+            subgroups: None,
+        }
+    }
+
+    /// The settings of a profile. Profiles in the config come first, then the built in
+    /// presets, so a preset can be changed by writing a profile with the same name.
+    pub fn profile_settings(&self, profile: &str) -> Option<FolderSettings> {
+        self.profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(profile))
+            .cloned()
+            .or_else(|| find_preset(profile).map(Preset::settings))
+    }
+
+    // The top level `[defaults]` with the group's own defaults on top.
+    fn group_settings(&self, group: &GroupSpec) -> FolderSettings {
+        let global_defaults = self.defaults.clone().unwrap_or_default();
+        match group.parts().0 {
+            Some(defaults) => global_defaults.merged_with(defaults),
+            None => global_defaults,
+        }
+    }
+
+    /// The settings a folder in `group` takes from the config before its own entry is
+    /// applied: the top level and group defaults, then the profile it names, if it
+    /// exists. The group needn't exist yet.
+    pub fn inherited_settings(&self, group: &str, profile: Option<&str>) -> FolderSettings {
+        let group_settings = match self.subgroups.as_ref().and_then(|groups| groups.get(group)) {
+            Some(group) => self.group_settings(group),
+            None => self.defaults.clone().unwrap_or_default(),
+        };
+        match profile.and_then(|profile| self.profile_settings(profile)) {
+            Some(profile_settings) => group_settings.merged_with(&profile_settings),
+            None => group_settings,
+        }
+    }
+
+    /// Works out the settings of every folder. Each setting comes from the first of
+    /// these that gives it:
+    ///
+    /// 1. The folder's own entry.
+    /// 2. The profile the entry names, if any.
+    /// 3. The `defaults` of the folder's group.
+    /// 4. The top level `[defaults]` table.
+    ///
+    /// Settings that none of them give are left at their built in defaults.
+    ///
+    /// # Returns
+    ///
+    /// The folders of each group, or an error if an entry names a profile that doesn't exist.
+    pub fn resolve_groups(&self) -> Result<Option<PathConfigMap>, ConfigError> {
+        let Some(subgroups) = &self.subgroups else {
+            return Ok(None);
+        };
+        let mut resolved = PathConfigMap::new();
+        for (name, group) in subgroups {
+            let group_settings = self.group_settings(group);
+            let entries = group.entries();
+
+            let mut configs = Vec::with_capacity(entries.len());
+            for entry in entries {
+                let mut settings = group_settings.clone();
+                if let Some(profile) = &entry.profile {
+                    let profile_settings = self.profile_settings(profile).ok_or_else(|| {
+                        ConfigError::UnknownProfile(profile.clone(), name.clone())
+                    })?;
+                    settings = settings.merged_with(&profile_settings);
+                }
+                settings = settings.merged_with(&entry.settings());
+                configs.push(PathConfig::from_settings(entry.directory.clone(), settings));
+            }
+            resolved.insert(name.clone(), configs);
+        }
+        Ok(Some(resolved))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct PathConfig {
     pub directory: PathBuf,
-    // Skip serialising if a PathConfig needs to be generated
-    // on the fly.
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions_to_delete: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions_to_keep: Option<Vec<String>>,
-    #[serde(default)]
+    // Files are also deleted if their name, or the name of a folder they're in,
+    // matches one of these globs, e.g. `*~` or `__pycache__`.
+    pub names_to_delete: Option<Vec<String>>,
     pub recursive: bool,
-    #[serde(default)]
     pub delete_hidden: bool,
+    // Only files last modified more than this many days ago are deleted.
+    pub older_than_days: Option<u64>,
+    // Labels used to select folders across groups, e.g. `--tag nightly`.
+    pub tags: Vec<String>,
+    // The name of the config group this entry was loaded from. Paths
+    // supplied directly on the command line don't belong to a group.
+    pub group: Option<String>,
+    // Category overrides from the top level of the config file, used when
+    // breaking the folder's contents down by category.
+    pub categories: Option<CategoryOverrides>,
+    // Keep files that won't be deleted in the scanned tree as well, so
+    // they can be shown alongside the deletion candidates.
+    pub track_kept_files: bool,
 }
 
 impl PathConfig {
+    /// Whether `other` picks out the same files, whichever group it came from.
+    pub fn has_same_filters(&self, other: &PathConfig) -> bool {
+        self.extensions_to_delete == other.extensions_to_delete
+            && self.extensions_to_keep == other.extensions_to_keep
+            && self.names_to_delete == other.names_to_delete
+            && self.recursive == other.recursive
+            && self.delete_hidden == other.delete_hidden
+            && self.older_than_days == other.older_than_days
+    }
+
     // Simplified constructor for manual instantiation with just the directory
     pub fn new(directory: PathBuf, use_relative_path: bool) -> Self {
         // Use absolute path by default
@@ -66,8 +392,147 @@ impl PathConfig {
             directory: directory_path,
             extensions_to_delete: None, // Default to None
             extensions_to_keep: None,   // Default to None
-            recursive: false,           // Default to false
-            delete_hidden: false,       // Default to false
+            names_to_delete: None,
+            recursive: false,     // Default to false
+            delete_hidden: false, // Default to false
+            older_than_days: None,
+            tags: Vec::new(),
+            group: None,
+            categories: None,
+            track_kept_files: false,
+        }
+    }
+
+    /// A folder from the config, with its settings already resolved.
+    pub fn from_settings(directory: PathBuf, settings: FolderSettings) -> Self {
+        PathConfig {
+            extensions_to_delete: settings.extensions_to_delete,
+            extensions_to_keep: settings.extensions_to_keep,
+            names_to_delete: settings.names_to_delete,
+            recursive: settings.recursive.unwrap_or(false),
+            delete_hidden: settings.delete_hidden.unwrap_or(false),
+            older_than_days: settings.older_than_days,
+            tags: settings.tags.unwrap_or_default(),
+            ..PathConfig::new(directory, true)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(content: &str) -> Result<PathConfigMap, ConfigError> {
+        let config: Config = toml::from_str(content).unwrap();
+        config.resolve_groups().map(Option::unwrap)
+    }
+
+    #[test]
+    fn test_settings_resolve_from_entry_then_profile_then_group_then_global() {
+        let groups = resolve(
+            r#"
+            [defaults]
+            extensions_to_delete = ["tmp"]
+            recursive = true
+            older_than_days = 90
+
+            [profiles.build-artifacts]
+            extensions_to_delete = ["o", "class"]
+            delete_hidden = true
+
+            [downloads.defaults]
+            older_than_days = 30
+
+            [[downloads.folders]]
+            directory = "/home/downloads"
+
+            [[downloads.folders]]
+            directory = "/home/builds"
+            profile = "build-artifacts"
+            recursive = false
+
+            [[logs]]
+            directory = "/var/log"
+            "#,
+        )
+        .unwrap();
+
+        let downloads = &groups["downloads"][0];
+        assert_eq!(
+            downloads.extensions_to_delete,
+            Some(vec!["tmp".to_string()])
+        );
+        assert_eq!(downloads.older_than_days, Some(30));
+        assert!(downloads.recursive);
+
+        let builds = &groups["downloads"][1];
+        assert_eq!(
+            builds.extensions_to_delete,
+            Some(vec!["o".to_string(), "class".to_string()])
+        );
+        assert!(builds.delete_hidden);
+        assert!(!builds.recursive);
+        assert_eq!(builds.older_than_days, Some(30));
+
+        let logs = &groups["logs"][0];
+        assert_eq!(logs.older_than_days, Some(90));
+        assert!(!logs.delete_hidden);
+    }
+
+    #[test]
+    fn test_unknown_profile_is_an_error() {
+        let result = resolve("[[logs]]\ndirectory = \"/var/log\"\nprofile = \"missing\"\n");
+        assert!(matches!(
+            result,
+            Err(ConfigError::UnknownProfile(profile, group)) if profile == "missing" && group == "logs"
+        ));
+    }
+
+    #[test]
+    fn test_profiles_fall_back_to_built_in_presets() {
+        let groups = resolve(
+            r#"
+            [profiles.rust-build]
+            names_to_delete = ["target", "*.profraw"]
+
+            [[projects]]
+            directory = "/home/src/app"
+            profile = "python-caches"
+
+            [[projects]]
+            directory = "/home/src/crate"
+            profile = "rust-build"
+            "#,
+        )
+        .unwrap();
+
+        let python = &groups["projects"][0];
+        assert_eq!(
+            python.extensions_to_delete,
+            Some(vec!["pyc".to_string(), "pyo".to_string()])
+        );
+        assert!(python
+            .names_to_delete
+            .as_ref()
+            .unwrap()
+            .contains(&"__pycache__".to_string()));
+        assert!(python.recursive);
+
+        // A profile in the config replaces the preset with the same name.
+        let rust = &groups["projects"][1];
+        assert_eq!(
+            rust.names_to_delete,
+            Some(vec!["target".to_string(), "*.profraw".to_string()])
+        );
+        assert!(!rust.recursive);
+    }
+
+    #[test]
+    fn test_group_must_be_entries_or_a_table() {
+        let result = toml::from_str::<Config>("logs = \"/var/log\"\n");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("expected a group of [[group]] entries or a group table"));
+    }
+}
@@ -0,0 +1,857 @@
+use super::config::{FolderSettings, PathConfig};
+use super::errors::ConfigError;
+use super::expansion::expand_directory;
+use super::get_user_home_dir;
+use super::parsing::{parse_config_from_str, ConfigFormat};
+use super::validation::{lint_edit, Diagnostic, Severity};
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use toml_edit::{value, Array, ArrayOfTables, DocumentMut, Item, Table};
+
+/// A single setting of a folder entry, as changed by `config set`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Directory(PathBuf),
+    ExtensionsToDelete(Option<Vec<String>>),
+    ExtensionsToKeep(Option<Vec<String>>),
+    NamesToDelete(Option<Vec<String>>),
+    Recursive(bool),
+    DeleteHidden(bool),
+    OlderThanDays(Option<u64>),
+    Tags(Vec<String>),
+    Profile(Option<String>),
+}
+
+// The key a setting is written under.
+fn setting_key(setting: &Setting) -> &'static str {
+    match setting {
+        Setting::Directory(_) => "directory",
+        Setting::ExtensionsToDelete(_) => "extensions_to_delete",
+        Setting::ExtensionsToKeep(_) => "extensions_to_keep",
+        Setting::NamesToDelete(_) => "names_to_delete",
+        Setting::Recursive(_) => "recursive",
+        Setting::DeleteHidden(_) => "delete_hidden",
+        Setting::OlderThanDays(_) => "older_than_days",
+        Setting::Tags(_) => "tags",
+        Setting::Profile(_) => "profile",
+    }
+}
+
+fn extensions_array(extensions: &[String]) -> Array {
+    extensions.iter().map(String::as_str).collect()
+}
+
+// Sets `key` to `item`, or removes it when there's nothing to write, so
+// settings that were switched off don't linger in the file.
+fn set_or_remove(table: &mut Table, key: &str, item: Option<Item>) {
+    match item {
+        Some(item) => {
+            table.insert(key, item);
+        }
+        None => {
+            table.remove(key);
+        }
+    }
+}
+
+fn apply_setting(table: &mut Table, setting: &Setting) {
+    let list_item =
+        |items: &Option<Vec<String>>| items.as_deref().map(|items| value(extensions_array(items)));
+    match setting {
+        Setting::Directory(directory) => {
+            table.insert("directory", value(directory.to_string_lossy().to_string()));
+        }
+        Setting::ExtensionsToDelete(extensions) => {
+            set_or_remove(table, "extensions_to_delete", list_item(extensions))
+        }
+        Setting::ExtensionsToKeep(extensions) => {
+            set_or_remove(table, "extensions_to_keep", list_item(extensions))
+        }
+        Setting::NamesToDelete(names) => set_or_remove(table, "names_to_delete", list_item(names)),
+        // Booleans are always written, so `false` can override a default of `true`.
+        Setting::Recursive(recursive) => {
+            table.insert("recursive", value(*recursive));
+        }
+        Setting::DeleteHidden(delete_hidden) => {
+            table.insert("delete_hidden", value(*delete_hidden));
+        }
+        Setting::OlderThanDays(days) => set_or_remove(
+            table,
+            "older_than_days",
+            days.map(|days| value(days as i64)),
+        ),
+        Setting::Tags(tags) => set_or_remove(
+            table,
+            "tags",
+            (!tags.is_empty()).then(|| value(extensions_array(tags))),
+        ),
+        Setting::Profile(profile) => set_or_remove(table, "profile", profile.as_deref().map(value)),
+    }
+}
+
+// Writes the settings of `config` that differ from the ones it inherits, leaving the
+// rest to fall back to the defaults. Switching off something that's inherited as on is
+// written out, e.g. `recursive = false`, as is `older_than_days = 0` for any age. Lists
+// can't be unset for a single folder, so an inherited list stays in place.
+fn write_settings(table: &mut Table, config: &PathConfig, inherited: &FolderSettings) {
+    let base = PathConfig::from_settings(config.directory.clone(), inherited.clone());
+    apply_setting(table, &Setting::Directory(config.directory.clone()));
+    let settings = [
+        (
+            config.extensions_to_delete != base.extensions_to_delete,
+            Setting::ExtensionsToDelete(config.extensions_to_delete.clone()),
+        ),
+        (
+            config.extensions_to_keep != base.extensions_to_keep,
+            Setting::ExtensionsToKeep(config.extensions_to_keep.clone()),
+        ),
+        (
+            config.names_to_delete != base.names_to_delete,
+            Setting::NamesToDelete(config.names_to_delete.clone()),
+        ),
+        (
+            config.recursive != base.recursive,
+            Setting::Recursive(config.recursive),
+        ),
+        (
+            config.delete_hidden != base.delete_hidden,
+            Setting::DeleteHidden(config.delete_hidden),
+        ),
+        (
+            config.older_than_days != base.older_than_days,
+            Setting::OlderThanDays(Some(config.older_than_days.unwrap_or(0))),
+        ),
+        (config.tags != base.tags, Setting::Tags(config.tags.clone())),
+    ];
+    for (differs, setting) in &settings {
+        match differs {
+            true => apply_setting(table, setting),
+            false => {
+                table.remove(setting_key(setting));
+            }
+        }
+    }
+}
+
+/// Checks a config can be edited in place. Only TOML configs can be, since they're
+/// the only ones whose comments and layout are kept.
+pub fn ensure_editable(config_path: &Path) -> Result<(), ConfigError> {
+    match ConfigFormat::from_path(config_path) {
+        ConfigFormat::Toml => Ok(()),
+        format => Err(ConfigError::NotEditable(config_path.to_path_buf(), format)),
+    }
+}
+
+fn load_document(config_path: &Path) -> Result<DocumentMut, ConfigError> {
+    ensure_editable(config_path)?;
+    let content = match config_path.exists() {
+        true => {
+            fs::read_to_string(config_path).map_err(|_| ConfigError::read_error(config_path))?
+        }
+        false => String::new(),
+    };
+    content
+        .parse()
+        .map_err(|e| ConfigError::parse_error(config_path, e))
+}
+
+// Checks the edited file still loads, and that the edit doesn't add any errors the
+// validation lints would find, before replacing the old one. A bad edit never leaves a
+// broken or dangerous config behind.
+fn save_document(config_path: &Path, document: &DocumentMut) -> Result<(), ConfigError> {
+    let contents = document.to_string();
+    parse_config_from_str(&contents, ConfigFormat::Toml)
+        .map_err(|e| ConfigError::parse_error(config_path, e))?;
+
+    let original = fs::read_to_string(config_path).unwrap_or_default();
+    let home_dir = get_user_home_dir().ok();
+    let (errors, warnings): (Vec<Diagnostic>, Vec<Diagnostic>) =
+        lint_edit(&original, &contents, home_dir.as_deref())
+            .into_iter()
+            .partition(|diagnostic| diagnostic.severity == Severity::Error);
+    if !errors.is_empty() {
+        let problems = errors.iter().map(Diagnostic::to_string).collect();
+        return Err(ConfigError::EditRejected(problems));
+    }
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    write_config_file(config_path, &contents)
+}
+
+// The folders of a group, whether it's written as `[[group]]` entries or as a
+// table with its folders under `[[group.folders]]`.
+fn folder_entries<'a>(
+    item: &'a mut Item,
+    group: &str,
+) -> Result<&'a mut ArrayOfTables, ConfigError> {
+    let entries = match item {
+        Item::Table(table) => table
+            .entry("folders")
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new())),
+        item => item,
+    };
+    entries
+        .as_array_of_tables_mut()
+        .ok_or_else(|| ConfigError::NotAGroup(group.to_string()))
+}
+
+fn group_entries<'a>(
+    document: &'a mut DocumentMut,
+    group: &str,
+) -> Result<&'a mut ArrayOfTables, ConfigError> {
+    let item = document
+        .get_mut(group)
+        .ok_or_else(|| ConfigError::FolderMapEmpty(group.to_string()))?;
+    folder_entries(item, group)
+}
+
+// Entries may be written with `~`, variables or a path that only leads to the folder
+// through a symlink, so they're compared by where they end up.
+fn is_entry_for(table: &Table, directory: &Path) -> bool {
+    entry_leads_to(table, directory, expand_directory)
+}
+
+// `expand` is passed in so entries can be matched without the real environment.
+fn entry_leads_to<F>(table: &Table, directory: &Path, expand: F) -> bool
+where
+    F: Fn(&Path) -> Result<Vec<PathBuf>, ConfigError>,
+{
+    let resolve = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let Some(entry) = table.get("directory").and_then(Item::as_str) else {
+        return false;
+    };
+    if Path::new(entry) == directory {
+        return true;
+    }
+    // A glob only stands for a single folder when it matches no others.
+    match expand(Path::new(entry)).as_deref() {
+        Ok([expanded]) => resolve(expanded) == resolve(directory),
+        _ => false,
+    }
+}
+
+/// Saves a folder's settings into a config group, keeping the rest of the file,
+/// comments included, as it was.
+///
+/// If the group already has an entry for the folder it's updated, otherwise a new
+/// entry is added to the group, which is created if it doesn't exist yet. The file
+/// itself is created if it's missing.
+///
+/// # Arguments
+///
+/// * `config_path` - The config file to update.
+/// * `group` - The name of the group to save the settings under.
+/// * `config` - The settings to save.
+pub fn save_to_group(
+    config_path: &Path,
+    group: &str,
+    config: &PathConfig,
+) -> Result<(), ConfigError> {
+    save_entry(config_path, group, config, true)
+}
+
+/// Adds a folder to a config group like `save_to_group`, but refuses to replace the
+/// settings of a folder the group already has unless `replace` is set.
+pub fn add_to_group(
+    config_path: &Path,
+    group: &str,
+    config: &PathConfig,
+    replace: bool,
+) -> Result<(), ConfigError> {
+    save_entry(config_path, group, config, replace)
+}
+
+fn save_entry(
+    config_path: &Path,
+    group: &str,
+    config: &PathConfig,
+    replace: bool,
+) -> Result<(), ConfigError> {
+    let mut document = load_document(config_path)?;
+    // A config that doesn't load is reported when it's saved, so until then it's
+    // treated as giving no defaults.
+    let current = parse_config_from_str(&document.to_string(), ConfigFormat::Toml).ok();
+    let inherited = |profile: Option<&str>| {
+        current
+            .as_ref()
+            .map(|current| current.inherited_settings(group, profile))
+            .unwrap_or_default()
+    };
+    let item = document
+        .entry(group)
+        .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+    let entries = folder_entries(item, group)?;
+
+    let existing = entries
+        .iter()
+        .position(|table| is_entry_for(table, &config.directory));
+    match existing.and_then(|index| entries.get_mut(index)) {
+        Some(_) if !replace => {
+            return Err(ConfigError::AlreadyInGroup(
+                group.to_string(),
+                config.directory.clone(),
+            ))
+        }
+        Some(table) => {
+            let profile = table.get("profile").and_then(Item::as_str);
+            let inherited = inherited(profile);
+            write_settings(table, config, &inherited);
+        }
+        None => {
+            let mut table = Table::new();
+            write_settings(&mut table, config, &inherited(None));
+            entries.push(table);
+        }
+    }
+
+    save_document(config_path, &document)
+}
+
+/// Removes a folder from a group, or the whole group if no directory is given. A
+/// group left without any folders is removed as well.
+///
+/// # Returns
+///
+/// The number of folders removed.
+pub fn remove_from_group(
+    config_path: &Path,
+    group: &str,
+    directory: Option<&Path>,
+) -> Result<usize, ConfigError> {
+    let mut document = load_document(config_path)?;
+    let entries = group_entries(&mut document, group)?;
+
+    let removed = match directory {
+        Some(directory) => {
+            let before = entries.len();
+            entries.retain(|table| !is_entry_for(table, directory));
+            match before - entries.len() {
+                0 => {
+                    return Err(ConfigError::DirectoryNotInGroup(
+                        group.to_string(),
+                        directory.to_path_buf(),
+                    ))
+                }
+                removed => removed,
+            }
+        }
+        None => entries.len(),
+    };
+    if directory.is_none() || entries.is_empty() {
+        document.remove(group);
+    }
+
+    save_document(config_path, &document)?;
+    Ok(removed)
+}
+
+/// Changes a setting of the folders in a group, or of a single folder if a directory
+/// is given.
+///
+/// # Returns
+///
+/// The number of folders changed.
+pub fn set_in_group(
+    config_path: &Path,
+    group: &str,
+    directory: Option<&Path>,
+    setting: &Setting,
+) -> Result<usize, ConfigError> {
+    let mut document = load_document(config_path)?;
+    let entries = group_entries(&mut document, group)?;
+    // Moving every folder of a group to the same directory is never what was meant.
+    if matches!(setting, Setting::Directory(_)) && directory.is_none() && entries.len() > 1 {
+        return Err(ConfigError::AmbiguousEntry(group.to_string()));
+    }
+
+    let mut changed = 0;
+    for table in entries.iter_mut() {
+        if directory.is_some_and(|directory| !is_entry_for(table, directory)) {
+            continue;
+        }
+        apply_setting(table, setting);
+        changed += 1;
+    }
+    if let (0, Some(directory)) = (changed, directory) {
+        return Err(ConfigError::DirectoryNotInGroup(
+            group.to_string(),
+            directory.to_path_buf(),
+        ));
+    }
+
+    save_document(config_path, &document)?;
+    Ok(changed)
+}
+
+// A new file next to the config to write the contents to first. The name is unique, so
+// two edits at once never write to the same file.
+fn create_temp_file(config_path: &Path) -> io::Result<(PathBuf, File)> {
+    let file_name = config_path
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy();
+    for attempt in 0..100 {
+        let temp_path =
+            config_path.with_file_name(format!(".{}.{}-{}.tmp", file_name, process::id(), attempt));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(io::Error::from(io::ErrorKind::AlreadyExists))
+}
+
+/// Writes a config file, creating the directory holding it if need be.
+///
+/// The contents are written to a temporary file next to the config first and then moved
+/// into place, so the config is never left half written. A config that's a symlink has
+/// the file it points to replaced, and the permissions of the old file are kept.
+pub fn write_config_file(config_path: &Path, contents: &str) -> Result<(), ConfigError> {
+    // Renaming onto a symlink would replace the link rather than the file it points to.
+    let target = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+    // New configs may go in a directory that doesn't exist yet, e.g. `~/.config/folder_cleaner`.
+    if let Some(parent) = target
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(|_| ConfigError::write_error(config_path))?;
+    }
+
+    let (temp_path, mut file) =
+        create_temp_file(&target).map_err(|_| ConfigError::write_error(config_path))?;
+    let permissions = fs::metadata(&target).map(|metadata| metadata.permissions());
+    let result = file
+        .write_all(contents.as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| match permissions {
+            Ok(permissions) => fs::set_permissions(&temp_path, permissions),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temp_path, &target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map_err(|_| ConfigError::write_error(config_path))
+}
+
+/// A folder to include in a new config file.
+pub struct NewConfigEntry {
+    pub group: String,
+    pub config: PathConfig,
+    // Written above the entry, e.g. to say which preset it came from
+    pub comment: Option<String>,
+}
+
+const NEW_CONFIG_HEADER: &str = "\
+# folder_cleaner configuration
+#
+# Each [[group]] entry describes a folder to clean. Run `folder_cleaner clean <group>`
+# to clean every folder in a group, or `folder_cleaner size <group>` to inspect them.
+#
+#   directory            - the folder to clean
+#   extensions_to_delete - only files with these extensions are deleted (any, if left out)
+#   extensions_to_keep   - files with these extensions are always kept
+#   names_to_delete      - files, or folders of files, with these names are deleted, e.g. `*~`
+#   older_than_days      - only files last modified more than this many days ago are deleted
+#   delete_hidden        - whether hidden files can be deleted
+#   recursive            - whether subfolders are cleaned as well
+#   profile              - a [profiles.<name>] table or built-in preset to take settings from
+#                          (see `folder_cleaner presets list`)
+#
+# Settings shared by every folder can go in a [defaults] table, and a group can be
+# written as a table with its own [<group>.defaults] and [[<group>.folders]] entries.
+";
+
+/// Renders a new, commented config file holding the given folders.
+pub fn render_new_config(entries: &[NewConfigEntry]) -> String {
+    let mut document = DocumentMut::new();
+    for entry in entries {
+        let mut table = Table::new();
+        write_settings(&mut table, &entry.config, &FolderSettings::default());
+        let comment = entry
+            .comment
+            .as_ref()
+            .map(|comment| format!("# {}\n", comment))
+            .unwrap_or_default();
+        table.decor_mut().set_prefix(format!("\n{}", comment));
+
+        let group = document
+            .entry(&entry.group)
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+        if let Some(group) = group.as_array_of_tables_mut() {
+            group.push(table);
+        }
+    }
+    format!("{}{}", NEW_CONFIG_HEADER, document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs::expansion::expand_with;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn downloads_config() -> PathConfig {
+        let mut config = PathConfig::new(PathBuf::from("/example/downloads"), true);
+        config.extensions_to_delete = Some(vec!["tmp".to_string(), "log".to_string()]);
+        config.older_than_days = Some(30);
+        config
+    }
+
+    #[test]
+    fn test_save_creates_file_and_group() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("folder_cleaner").join("config.toml");
+
+        save_to_group(&config_path, "downloads", &downloads_config()).unwrap();
+
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert_eq!(
+            saved,
+            "[[downloads]]\n\
+             directory = \"/example/downloads\"\n\
+             extensions_to_delete = [\"tmp\", \"log\"]\n\
+             older_than_days = 30\n"
+        );
+    }
+
+    #[test]
+    fn test_save_updates_matching_entry_and_keeps_comments() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "# My folders\n\
+             [[downloads]]\n\
+             directory = \"/example/downloads\"\n\
+             extensions_to_keep = [\"pdf\"]\n\
+             \n\
+             [[downloads]]\n\
+             directory = \"/example/other\"\n",
+        )
+        .unwrap();
+
+        save_to_group(&config_path, "downloads", &downloads_config()).unwrap();
+
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.starts_with("# My folders\n"));
+        assert!(!saved.contains("extensions_to_keep"));
+        assert_eq!(saved.matches("[[downloads]]").count(), 2);
+        assert!(saved.contains("directory = \"/example/other\""));
+        assert!(saved.contains("older_than_days = 30"));
+    }
+
+    #[test]
+    fn test_new_config_is_commented_and_parses() {
+        let mut logs = PathConfig::new(PathBuf::from("/var/log/app"), true);
+        logs.extensions_to_delete = Some(vec!["log".to_string()]);
+        let entries = [
+            NewConfigEntry {
+                group: "downloads".to_string(),
+                config: downloads_config(),
+                comment: Some("Installers and archives".to_string()),
+            },
+            NewConfigEntry {
+                group: "logs".to_string(),
+                config: logs,
+                comment: None,
+            },
+        ];
+
+        let rendered = render_new_config(&entries);
+        assert!(rendered.starts_with("# folder_cleaner configuration\n"));
+        assert!(rendered.contains(
+            "\n# Installers and archives\n[[downloads]]\ndirectory = \"/example/downloads\"\n"
+        ));
+
+        let parsed: crate::configs::config::Config = toml::from_str(&rendered).unwrap();
+        let groups = parsed.resolve_groups().unwrap().unwrap();
+        assert_eq!(groups["downloads"][0].older_than_days, Some(30));
+        assert_eq!(groups["logs"][0].directory, PathBuf::from("/var/log/app"));
+    }
+
+    const TWO_FOLDERS: &str = "# Cleaned every night\n\
+         [[logs]]\n\
+         directory = \"/var/log/app\" # the main app\n\
+         extensions_to_delete = [\"log\"]\n\
+         \n\
+         [[logs]]\n\
+         directory = \"/var/log/web\"\n\
+         \n\
+         [[downloads]]\n\
+         directory = \"/example/downloads\"\n";
+
+    #[test]
+    fn test_set_changes_every_folder_or_only_the_chosen_one() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, TWO_FOLDERS).unwrap();
+
+        let changed = set_in_group(
+            &config_path,
+            "logs",
+            None,
+            &Setting::OlderThanDays(Some(14)),
+        )
+        .unwrap();
+        assert_eq!(changed, 2);
+
+        let web = Path::new("/var/log/web");
+        let tags = Setting::Tags(vec!["nightly".to_string()]);
+        assert_eq!(
+            set_in_group(&config_path, "logs", Some(web), &tags).unwrap(),
+            1
+        );
+
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.starts_with("# Cleaned every night\n"));
+        assert!(saved.contains("directory = \"/var/log/app\" # the main app\n"));
+        assert_eq!(saved.matches("older_than_days = 14").count(), 2);
+        assert_eq!(saved.matches("tags = [\"nightly\"]").count(), 1);
+
+        let moved = Setting::Directory(PathBuf::from("/var/log/other"));
+        let result = set_in_group(&config_path, "logs", None, &moved);
+        assert!(matches!(result, Err(ConfigError::AmbiguousEntry(_))));
+    }
+
+    #[test]
+    fn test_remove_a_folder_then_the_emptied_group() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, TWO_FOLDERS).unwrap();
+
+        let app = Path::new("/var/log/app");
+        assert_eq!(
+            remove_from_group(&config_path, "logs", Some(app)).unwrap(),
+            1
+        );
+        let result = remove_from_group(&config_path, "logs", Some(app));
+        assert!(matches!(
+            result,
+            Err(ConfigError::DirectoryNotInGroup(_, _))
+        ));
+
+        let web = Path::new("/var/log/web");
+        assert_eq!(
+            remove_from_group(&config_path, "logs", Some(web)).unwrap(),
+            1
+        );
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(!saved.contains("[[logs]]"));
+        assert!(saved.contains("[[downloads]]"));
+
+        assert_eq!(
+            remove_from_group(&config_path, "downloads", None).unwrap(),
+            1
+        );
+        let result = remove_from_group(&config_path, "downloads", None);
+        assert!(matches!(result, Err(ConfigError::FolderMapEmpty(_))));
+    }
+
+    #[test]
+    fn test_edits_that_break_the_config_are_not_written() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "[[logs]]\ndirectory = \"/var/log\"\ntypo = 1\n",
+        )
+        .unwrap();
+
+        let result = set_in_group(&config_path, "logs", None, &Setting::Recursive(true));
+        assert!(matches!(result, Err(ConfigError::ParseError(_, _))));
+        assert!(!fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("recursive"));
+    }
+
+    #[test]
+    fn test_edits_that_add_lint_errors_are_not_written() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, "[[logs]]\ndirectory = \"/var/log\"\n").unwrap();
+
+        let setting = Setting::ExtensionsToKeep(Some(vec!["log".to_string()]));
+        set_in_group(&config_path, "logs", None, &setting).unwrap();
+        let setting = Setting::ExtensionsToDelete(Some(vec!["LOG".to_string()]));
+        let result = set_in_group(&config_path, "logs", None, &setting);
+        assert!(
+            matches!(result, Err(ConfigError::EditRejected(problems)) if problems[0].contains("'LOG'"))
+        );
+        assert!(!fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("extensions_to_delete"));
+
+        // Problems the file already had don't stop other edits
+        fs::write(
+            &config_path,
+            "[[logs]]\ndirectory = \"/\"\n\n[[cache]]\ndirectory = \"/tmp\"\n",
+        )
+        .unwrap();
+        set_in_group(&config_path, "cache", None, &Setting::Recursive(true)).unwrap();
+    }
+
+    #[test]
+    fn test_edits_find_entries_by_where_they_lead() {
+        let dir = tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        fs::create_dir(&logs).unwrap();
+        let logs = fs::canonicalize(logs).unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            format!(
+                "[[logs]]\ndirectory = \"{}/./logs\"\n\n\
+                 [[logs]]\ndirectory = \"/var/log\"\n",
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+
+        let changed = set_in_group(
+            &config_path,
+            "logs",
+            Some(&logs),
+            &Setting::OlderThanDays(Some(3)),
+        )
+        .unwrap();
+        assert_eq!(changed, 1);
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("/./logs\"\nolder_than_days = 3"));
+
+        // Variables are looked up rather than read from the environment
+        let document: DocumentMut = "directory = \"$PROJECT/logs\"\n".parse().unwrap();
+        let project = dir.path().to_string_lossy().to_string();
+        let expand = |entry: &Path| {
+            expand_with(entry, None, |name| {
+                (name == "PROJECT").then(|| project.clone())
+            })
+        };
+        assert!(entry_leads_to(document.as_table(), &logs, expand));
+        assert!(!entry_leads_to(
+            document.as_table(),
+            Path::new("/var/log"),
+            expand
+        ));
+    }
+
+    #[test]
+    fn test_saved_settings_only_override_what_is_inherited() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "[defaults]\nolder_than_days = 30\n\n\
+             [downloads.defaults]\nrecursive = true\nextensions_to_delete = [\"tmp\", \"log\"]\n",
+        )
+        .unwrap();
+
+        let mut config = downloads_config();
+        config.older_than_days = None;
+        save_to_group(&config_path, "downloads", &config).unwrap();
+
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.ends_with(
+            "[[downloads.folders]]\n\
+             directory = \"/example/downloads\"\n\
+             recursive = false\n\
+             older_than_days = 0\n"
+        ));
+        let loaded = parse_config_from_str(&saved, ConfigFormat::Toml).unwrap();
+        let saved_config = &loaded.resolve_groups().unwrap().unwrap()["downloads"][0];
+        assert!(!saved_config.recursive);
+        assert_eq!(
+            saved_config.extensions_to_delete,
+            config.extensions_to_delete
+        );
+    }
+
+    #[test]
+    fn test_add_only_replaces_a_folder_when_asked() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        add_to_group(&config_path, "downloads", &downloads_config(), false).unwrap();
+
+        let mut changed = downloads_config();
+        changed.older_than_days = Some(7);
+        let result = add_to_group(&config_path, "downloads", &changed, false);
+        assert!(matches!(result, Err(ConfigError::AlreadyInGroup(_, _))));
+        assert!(fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("older_than_days = 30"));
+
+        add_to_group(&config_path, "downloads", &changed, true).unwrap();
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.contains("older_than_days = 7"));
+        assert_eq!(saved.matches("[[downloads]]").count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_writes_follow_symlinks_and_keep_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let dotfiles = dir.path().join("dotfiles.toml");
+        let config_path = dir.path().join("config.toml");
+        fs::write(&dotfiles, "").unwrap();
+        fs::set_permissions(&dotfiles, fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(&dotfiles, &config_path).unwrap();
+
+        save_to_group(&config_path, "downloads", &downloads_config()).unwrap();
+
+        assert!(fs::symlink_metadata(&config_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(fs::read_to_string(&dotfiles)
+            .unwrap()
+            .contains("[[downloads]]"));
+        let mode = fs::metadata(&dotfiles).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_save_rejects_a_key_that_is_not_a_group() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(&config_path, "downloads = 1\n").unwrap();
+
+        let result = save_to_group(&config_path, "downloads", &downloads_config());
+        assert!(matches!(result, Err(ConfigError::NotAGroup(_))));
+    }
+
+    #[test]
+    fn test_edits_reach_folders_of_a_group_table() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            "[logs.defaults]\nrecursive = true\n\n[[logs.folders]]\ndirectory = \"/var/log\"\n",
+        )
+        .unwrap();
+
+        set_in_group(&config_path, "logs", None, &Setting::Recursive(false)).unwrap();
+        save_to_group(&config_path, "logs", &downloads_config()).unwrap();
+
+        let saved = fs::read_to_string(&config_path).unwrap();
+        assert!(saved.starts_with("[logs.defaults]\nrecursive = true\n"));
+        let config = parse_config_from_str(&saved, ConfigFormat::Toml).unwrap();
+        let groups = config.resolve_groups().unwrap().unwrap();
+        assert_eq!(groups["logs"].len(), 2);
+        assert!(!groups["logs"][0].recursive);
+        // The saved folder doesn't clean subfolders, so it overrides the group's default
+        assert!(!groups["logs"][1].recursive);
+    }
+}
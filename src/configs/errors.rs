@@ -1,5 +1,6 @@
 // TODO: Add an additional error message detailing how to update the config file, where appropriate
-use std::path::PathBuf;
+use super::parsing::ConfigFormat;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub enum ConfigError {
@@ -8,25 +9,50 @@ pub enum ConfigError {
     ParseError(PathBuf, Box<dyn std::error::Error>),
     ConfigNotFound(PathBuf),
     FolderMapEmpty(String),
+    WriteError(PathBuf),
+    NotAGroup(String),
+    AlreadyExists(PathBuf),
+    ValidationFailed(usize),
+    DirectoryNotInGroup(String, PathBuf),
+    AmbiguousEntry(String),
+    UndefinedVariable(String, PathBuf),
+    InvalidDirectoryPattern(String, &'static str),
+    UnmatchedDirectoryPattern(String),
+    UnknownProfile(String, String),
+    NotEditable(PathBuf, ConfigFormat),
+    ProjectOverride(String, PathBuf),
+    OutsideProject(PathBuf, PathBuf),
+    AlreadyInGroup(String, PathBuf),
+    EditRejected(Vec<String>),
 }
 
 impl ConfigError {
-    pub fn read_error(path: &PathBuf) -> Self {
+    pub fn read_error(path: &Path) -> Self {
         ConfigError::ReadError(path.to_path_buf())
     }
 
-    pub fn parse_error(path: &PathBuf, error: toml::de::Error) -> Self {
+    pub fn parse_error<E: std::error::Error + 'static>(path: &Path, error: E) -> Self {
         ConfigError::ParseError(path.to_path_buf(), Box::new(error))
     }
+
+    pub fn write_error(path: &Path) -> Self {
+        ConfigError::WriteError(path.to_path_buf())
+    }
 }
 
 impl std::fmt::Display for ConfigError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ConfigError::UserDirNotFound => write!(f, "User directory not found."),
-            ConfigError::ReadError(path) => {
+            ConfigError::ReadError(path) if path.exists() => {
                 write!(f, "Failed to read config file at '{}'.", path.display())
             }
+            ConfigError::ReadError(path) => write!(
+                f,
+                "There's no config file at '{}'. Run `folder_cleaner config init` to \
+                 create one.",
+                path.display()
+            ),
             ConfigError::ParseError(path, err) => write!(
                 f,
                 "Failed to parse config file at '{}': {}.",
@@ -43,6 +69,88 @@ impl std::fmt::Display for ConfigError {
                     subgroup
                 )
             }
+            ConfigError::WriteError(path) => {
+                write!(f, "Failed to write config file at '{}'.", path.display())
+            }
+            ConfigError::AlreadyExists(path) => write!(
+                f,
+                "A config file already exists at '{}'. Use --force to replace it.",
+                path.display()
+            ),
+            ConfigError::ValidationFailed(count) => write!(
+                f,
+                "Found {} problem(s) in your config. Fix them and run `folder_cleaner config validate` again.",
+                count
+            ),
+            ConfigError::DirectoryNotInGroup(group, directory) => write!(
+                f,
+                "The group '{}' has no folder at '{}'.",
+                group,
+                directory.display()
+            ),
+            ConfigError::AmbiguousEntry(group) => write!(
+                f,
+                "The group '{}' has several folders. Use --dir to pick the one to change.",
+                group
+            ),
+            ConfigError::UndefinedVariable(name, directory) => write!(
+                f,
+                "The environment variable '{}' used in the directory '{}' isn't set.",
+                name,
+                directory.display()
+            ),
+            ConfigError::InvalidDirectoryPattern(pattern, reason) => write!(
+                f,
+                "The directory pattern '{}' isn't valid: {}.",
+                pattern, reason
+            ),
+            ConfigError::UnmatchedDirectoryPattern(pattern) => write!(
+                f,
+                "The directory pattern '{}' doesn't match any folders.",
+                pattern
+            ),
+            ConfigError::UnknownProfile(profile, group) => write!(
+                f,
+                "The group '{}' uses the profile '{}', but there's no [profiles.{}] table \
+                 or built in preset with that name. See `folder_cleaner presets list`.",
+                group, profile, profile
+            ),
+            ConfigError::AlreadyInGroup(group, directory) => write!(
+                f,
+                "The group '{}' already has a folder at '{}'. Use --replace to replace its \
+                 settings, or `folder_cleaner config set` to change them one at a time.",
+                group,
+                directory.display()
+            ),
+            ConfigError::EditRejected(problems) => write!(
+                f,
+                "The change wasn't saved, as it would leave these problems in your config:\n  {}",
+                problems.join("\n  ")
+            ),
+            ConfigError::ProjectOverride(name, path) => write!(
+                f,
+                "The project config at '{}' redefines '{}' from your own config. Rename it \
+                 there, or pass --allow-project-overrides if you trust the project.",
+                path.display(),
+                name
+            ),
+            ConfigError::OutsideProject(directory, path) => write!(
+                f,
+                "The project config at '{}' points at '{}', which is outside the project. \
+                 Project configs may only clean folders beneath them.",
+                path.display(),
+                directory.display()
+            ),
+            ConfigError::NotEditable(path, format) => write!(
+                f,
+                "'{}' is a {} config, and only TOML configs can be edited. Convert it with \
+                 `folder_cleaner config convert --to toml`, or edit it by hand.",
+                path.display(),
+                format
+            ),
+            ConfigError::NotAGroup(key) => {
+                write!(f, "The key '{}' in your config file isn't a group.", key)
+            }
         }
     }
 }
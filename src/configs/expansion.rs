@@ -0,0 +1,193 @@
+use super::errors::ConfigError;
+use super::get_user_home_dir;
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+// Characters that turn a directory into a glob, e.g. `/data/*/tmp`.
+const GLOB_CHARACTERS: [char; 3] = ['*', '?', '['];
+
+// Replaces `$VAR` and `${VAR}` with the value of the variable. A `$` that isn't
+// followed by a name is left alone.
+fn expand_variables<F>(text: &str, directory: &Path, lookup: F) -> Result<String, ConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            let value = lookup(name).ok_or_else(|| {
+                ConfigError::UndefinedVariable(name.to_string(), directory.to_path_buf())
+            })?;
+            expanded.push_str(&value);
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// Replaces a leading `~` with the home directory. `~user` isn't supported, so it's left as is.
+fn expand_home(text: &str, home_dir: Option<&Path>) -> Result<String, ConfigError> {
+    let rest = match text.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => return Ok(text.to_string()),
+    };
+    let home_dir = home_dir.ok_or(ConfigError::UserDirNotFound)?;
+    Ok(format!("{}{}", home_dir.to_string_lossy(), rest))
+}
+
+fn expand_glob(pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+    let paths = glob::glob(pattern)
+        .map_err(|error| ConfigError::InvalidDirectoryPattern(pattern.to_string(), error.msg))?;
+    // Only folders can be cleaned, so any files the pattern matches are skipped.
+    let directories: Vec<PathBuf> = paths
+        .filter_map(Result::ok)
+        .filter(|path| path.is_dir())
+        .collect();
+    match directories.is_empty() {
+        true => Err(ConfigError::UnmatchedDirectoryPattern(pattern.to_string())),
+        false => Ok(directories),
+    }
+}
+
+/// Expands `directory` as `expand_directory` does, with the home directory and
+/// variables given rather than taken from the environment.
+pub fn expand_with<F>(
+    directory: &Path,
+    home_dir: Option<&Path>,
+    lookup: F,
+) -> Result<Vec<PathBuf>, ConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    // Paths that aren't valid UTF-8 can't hold anything to expand.
+    let Some(text) = directory.to_str() else {
+        return Ok(vec![directory.to_path_buf()]);
+    };
+
+    let expanded = expand_home(&expand_variables(text, directory, lookup)?, home_dir)?;
+    // Folder names may contain glob characters themselves, e.g. `Photos [2019]`.
+    match expanded.contains(GLOB_CHARACTERS) && !Path::new(&expanded).exists() {
+        true => expand_glob(&expanded),
+        false => Ok(vec![PathBuf::from(expanded)]),
+    }
+}
+
+/// Expands a `directory` from the config into the folders it refers to: a leading `~`
+/// becomes the home directory, `$VAR` and `${VAR}` are replaced by the variable's
+/// value, and globs such as `/data/*/tmp` give every matching folder, in name order.
+/// A path that exists is used as it is, even if it looks like a glob.
+///
+/// # Returns
+///
+/// The expanded folders, or an error if a variable isn't set, or the glob is invalid or
+/// doesn't match any folders.
+pub fn expand_directory(directory: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let home_dir = get_user_home_dir().ok();
+    expand_with(directory, home_dir.as_deref(), |name| env::var(name).ok())
+}
+
+/// Whether a directory is written relative to something other than the current
+/// folder, such as `~` or an environment variable.
+pub fn is_anchored_elsewhere(directory: &Path) -> bool {
+    directory
+        .to_str()
+        .is_some_and(|text| text.starts_with('~') || text.starts_with('$'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DATA" => Some("/srv/data".to_string()),
+            "USER" => Some("sam".to_string()),
+            _ => None,
+        }
+    }
+
+    fn expand(directory: &str) -> Result<Vec<PathBuf>, ConfigError> {
+        expand_with(Path::new(directory), Some(Path::new("/home/sam")), lookup)
+    }
+
+    #[test]
+    fn test_home_and_variables_are_expanded() {
+        assert_eq!(expand("~").unwrap(), vec![PathBuf::from("/home/sam")]);
+        assert_eq!(
+            expand("~/Downloads").unwrap(),
+            vec![PathBuf::from("/home/sam/Downloads")]
+        );
+        assert_eq!(
+            expand("$DATA/${USER}_cache/$").unwrap(),
+            vec![PathBuf::from("/srv/data/sam_cache/$")]
+        );
+        assert_eq!(expand("~other/x").unwrap(), vec![PathBuf::from("~other/x")]);
+    }
+
+    #[test]
+    fn test_undefined_variable_is_an_error() {
+        let result = expand("${MISSING}/tmp");
+        assert!(matches!(
+            result,
+            Err(ConfigError::UndefinedVariable(name, _)) if name == "MISSING"
+        ));
+    }
+
+    #[test]
+    fn test_globs_match_folders_in_name_order() {
+        let root = tempdir().unwrap();
+        for folder in ["b/tmp", "a/tmp", "c/other"] {
+            fs::create_dir_all(root.path().join(folder)).unwrap();
+        }
+        fs::write(root.path().join("d"), "").unwrap();
+
+        let pattern = format!("{}/*/tmp", root.path().display());
+        assert_eq!(
+            expand(&pattern).unwrap(),
+            vec![root.path().join("a/tmp"), root.path().join("b/tmp")]
+        );
+        let files = format!("{}/d*", root.path().display());
+        assert!(matches!(
+            expand(&files),
+            Err(ConfigError::UnmatchedDirectoryPattern(pattern)) if pattern == files
+        ));
+    }
+
+    #[test]
+    fn test_existing_folders_are_not_read_as_globs() {
+        let root = tempdir().unwrap();
+        for folder in ["Photos [2019]", "Photos 1"] {
+            fs::create_dir_all(root.path().join(folder)).unwrap();
+        }
+
+        let literal = root.path().join("Photos [2019]");
+        assert_eq!(
+            expand(&literal.to_string_lossy()).unwrap(),
+            vec![literal.clone()]
+        );
+        let missing = root.path().join("Photos [2020]");
+        assert!(expand(&missing.to_string_lossy()).is_err());
+    }
+}
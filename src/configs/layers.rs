@@ -0,0 +1,496 @@
+use super::config::Config;
+use super::errors::ConfigError;
+use super::expansion::is_anchored_elsewhere;
+use super::location::{ConfigLocation, ConfigSource};
+use super::parsing::extract_user_config_from_path;
+use super::{get_user_config_path, CONFIG_FILE_NAME};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::canonicalize;
+use std::path::{Component, Path, PathBuf};
+
+/// The config shared by every user on the machine, merged in before anything else.
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/folder_cleaner/config.toml";
+
+/// Where a config layer comes from. Later kinds take priority over earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerKind {
+    System,
+    User,
+    // A `.nuke.toml` found in the current directory or one of its parents
+    Project,
+}
+
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Padded, so the kinds line up when listed.
+        f.pad(match self {
+            LayerKind::System => "system",
+            LayerKind::User => "user",
+            LayerKind::Project => "project",
+        })
+    }
+}
+
+/// A single config file that makes up part of the merged configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLayer {
+    pub path: PathBuf,
+    pub kind: LayerKind,
+    // Project configs may only replace system and user groups once the user
+    // has allowed it, e.g. with `--allow-project-overrides`.
+    pub may_override: bool,
+}
+
+impl ConfigLayer {
+    pub fn new(path: PathBuf, kind: LayerKind) -> ConfigLayer {
+        ConfigLayer {
+            path,
+            kind,
+            may_override: false,
+        }
+    }
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (canonicalize(a), canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// Every `.nuke.toml` from `start_dir` up to the root, the outermost first.
+fn find_project_configs(start_dir: &Path) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = start_dir
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .filter(|path| path.is_file())
+        .collect();
+    found.reverse();
+    found
+}
+
+fn discover_from(
+    location: &ConfigLocation,
+    system_path: &Path,
+    legacy_path: Option<&Path>,
+    start_dir: Option<&Path>,
+    allow_project_overrides: bool,
+) -> Vec<ConfigLayer> {
+    // A file picked explicitly is used on its own, so it behaves the same wherever it's run.
+    if matches!(
+        location.source,
+        ConfigSource::CommandLine | ConfigSource::EnvVar
+    ) {
+        return vec![ConfigLayer::new(location.path.clone(), LayerKind::User)];
+    }
+
+    let mut layers = Vec::new();
+    if system_path.is_file() {
+        layers.push(ConfigLayer::new(
+            system_path.to_path_buf(),
+            LayerKind::System,
+        ));
+    }
+    if location.path.is_file() {
+        layers.push(ConfigLayer::new(location.path.clone(), LayerKind::User));
+    }
+    // The legacy user config lives in the home directory, so walking up from inside
+    // it would find it again. It belongs to the user even when it's not the one in use.
+    let is_user_config = |path: &Path| {
+        is_same_file(path, &location.path)
+            || legacy_path.is_some_and(|legacy| is_same_file(path, legacy))
+    };
+    let project_configs = start_dir.map(find_project_configs).unwrap_or_default();
+    layers.extend(
+        project_configs
+            .into_iter()
+            .filter(|path| !is_user_config(path))
+            .map(|path| ConfigLayer {
+                may_override: allow_project_overrides,
+                ..ConfigLayer::new(path, LayerKind::Project)
+            }),
+    );
+    layers
+}
+
+/// Lists the config files to merge, from the lowest priority to the highest: the system
+/// config, the user config and then any `.nuke.toml` in the current directory or its
+/// parents, the closest last. Files given with `--config` or `$FOLDER_CLEANER_CONFIG`
+/// are used on their own instead.
+///
+/// # Arguments
+///
+/// * `location` - The user config picked by `resolve_config_path`.
+/// * `allow_project_overrides` - Whether project configs may replace system and user
+///   groups and profiles of the same name.
+pub fn discover_config_layers(
+    location: &ConfigLocation,
+    allow_project_overrides: bool,
+) -> Vec<ConfigLayer> {
+    let current_dir = std::env::current_dir().ok();
+    let legacy_path = get_user_config_path(CONFIG_FILE_NAME).ok();
+    discover_from(
+        location,
+        Path::new(SYSTEM_CONFIG_PATH),
+        legacy_path.as_deref(),
+        current_dir.as_deref(),
+        allow_project_overrides,
+    )
+}
+
+// Relative directories in a project config point at folders next to the file,
+// wherever the tool is run from.
+fn resolve_relative_directories(config: &mut Config, layer_path: &Path) {
+    let Some(base_dir) = layer_path.parent() else {
+        return;
+    };
+    let entries = config
+        .subgroups
+        .iter_mut()
+        .flat_map(|groups| groups.values_mut())
+        .flat_map(|group| group.entries_mut().iter_mut());
+    for entry in entries {
+        if entry.directory.is_relative() && !is_anchored_elsewhere(&entry.directory) {
+            entry.directory = base_dir.join(&entry.directory);
+        }
+    }
+}
+
+// Resolves `.` and `..` without touching the disk, so the path can be compared
+// with the folder it's meant to stay inside.
+fn normalise_lexically(path: &Path) -> PathBuf {
+    let mut normalised = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalised.pop();
+            }
+            component => normalised.push(component),
+        }
+    }
+    normalised
+}
+
+fn is_inside(directory: &Path, root: &Path) -> bool {
+    if !normalise_lexically(directory).starts_with(root) {
+        return false;
+    }
+    // Symlinks inside the project can still lead out of it.
+    match (canonicalize(directory), canonicalize(root)) {
+        (Ok(directory), Ok(root)) => directory.starts_with(root),
+        _ => true,
+    }
+}
+
+// A project config comes with whatever was checked out, so it may only clean
+// folders beneath the folder it's in.
+fn check_project_directories(config: &Config, layer_path: &Path) -> Result<(), ConfigError> {
+    let root = layer_path.parent().unwrap_or(Path::new("/"));
+    let entries = config
+        .subgroups
+        .iter()
+        .flat_map(|groups| groups.values())
+        .flat_map(|group| group.entries());
+    for entry in entries {
+        if is_anchored_elsewhere(&entry.directory) || !is_inside(&entry.directory, root) {
+            return Err(ConfigError::OutsideProject(
+                entry.directory.clone(),
+                layer_path.to_path_buf(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The names of the groups and profiles defined in `config`.
+fn defined_names(config: &Config) -> HashSet<String> {
+    let groups = config.subgroups.iter().flat_map(|groups| groups.keys());
+    let profiles = config.profiles.iter().flat_map(|profiles| profiles.keys());
+    groups.chain(profiles).cloned().collect()
+}
+
+// Merges two maps, with the entries in `overlay` replacing those of the same name.
+fn merge_maps<M, E>(base: Option<M>, overlay: Option<M>) -> Option<M>
+where
+    M: Extend<E> + IntoIterator<Item = E>,
+{
+    match (base, overlay) {
+        (Some(mut base), Some(overlay)) => {
+            base.extend(overlay);
+            Some(base)
+        }
+        (map, None) | (None, map) => map,
+    }
+}
+
+/// Merges `overlay` on top of `base`. A group or profile in `overlay` replaces the one
+/// of the same name in `base` entirely, while categories are overridden one by one and
+/// `[defaults]` setting by setting.
+///
+/// Defaults and profiles are only applied once every layer is merged, so a user's
+/// `[defaults]` also apply to the groups of a project config.
+pub fn merge_configs(base: Config, overlay: Config) -> Config {
+    let defaults = match (base.defaults, overlay.defaults) {
+        (Some(base), Some(overlay)) => Some(base.merged_with(&overlay)),
+        (defaults, None) | (None, defaults) => defaults,
+    };
+    Config {
+        categories: merge_maps(base.categories, overlay.categories),
+        defaults,
+        profiles: merge_maps(base.profiles, overlay.profiles),
+        subgroups: merge_maps(base.subgroups, overlay.subgroups),
+    }
+}
+
+/// Reads every layer and merges them into a single config.
+///
+/// # Returns
+///
+/// The merged config, or an error if any layer couldn't be read or parsed, or if there
+/// are no layers at all, in which case `fallback_path` is reported as missing. Project
+/// layers are also rejected if they point outside their folder, or replace a system or
+/// user group or profile without being allowed to.
+pub fn load_layered_config(
+    layers: &[ConfigLayer],
+    fallback_path: &Path,
+) -> Result<Config, ConfigError> {
+    if layers.is_empty() {
+        return Err(ConfigError::read_error(fallback_path));
+    }
+
+    let mut merged = Config::empty();
+    // The groups and profiles from the system and user configs, which project configs
+    // may not replace
+    let mut protected = HashSet::new();
+    for layer in layers {
+        let mut config = extract_user_config_from_path(&layer.path)?;
+        match layer.kind {
+            LayerKind::Project => {
+                resolve_relative_directories(&mut config, &layer.path);
+                check_project_directories(&config, &layer.path)?;
+                let replaced = defined_names(&config)
+                    .into_iter()
+                    .filter(|name| protected.contains(name))
+                    .min();
+                if let Some(name) = replaced.filter(|_| !layer.may_override) {
+                    return Err(ConfigError::ProjectOverride(name, layer.path.clone()));
+                }
+            }
+            LayerKind::System | LayerKind::User => {
+                protected.extend(defined_names(&config));
+            }
+        }
+        merged = merge_configs(merged, config);
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_layers_ordered_from_system_to_closest_project() {
+        let root = tempdir().unwrap();
+        let system = root.path().join("etc/config.toml");
+        let user = root.path().join("home/.config/folder_cleaner/config.toml");
+        let outer = root.path().join("repo/.nuke.toml");
+        let inner = root.path().join("repo/app/.nuke.toml");
+        for path in [&system, &user, &outer, &inner] {
+            write_file(path, "");
+        }
+        let location = ConfigLocation {
+            path: user.clone(),
+            source: ConfigSource::Xdg,
+        };
+
+        let start_dir = root.path().join("repo/app/src");
+        fs::create_dir_all(&start_dir).unwrap();
+        let layers = discover_from(&location, &system, None, Some(&start_dir), false);
+        let paths: Vec<&PathBuf> = layers.iter().map(|layer| &layer.path).collect();
+        assert_eq!(paths, vec![&system, &user, &outer, &inner]);
+
+        let explicit = ConfigLocation {
+            path: user.clone(),
+            source: ConfigSource::CommandLine,
+        };
+        assert_eq!(
+            discover_from(&explicit, &system, None, Some(&start_dir), false).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_legacy_user_config_not_repeated_as_project_config() {
+        let home = tempdir().unwrap();
+        let legacy = home.path().join(CONFIG_FILE_NAME);
+        write_file(&legacy, "");
+        let location = ConfigLocation {
+            path: legacy.clone(),
+            source: ConfigSource::Legacy,
+        };
+
+        let layers = discover_from(
+            &location,
+            Path::new("/missing"),
+            Some(&legacy),
+            Some(home.path()),
+            false,
+        );
+        assert_eq!(layers, vec![ConfigLayer::new(legacy, LayerKind::User)]);
+    }
+
+    #[test]
+    fn test_unused_legacy_config_is_not_a_project_config() {
+        let home = tempdir().unwrap();
+        let legacy = home.path().join(CONFIG_FILE_NAME);
+        let user = home.path().join(".config/folder_cleaner/config.toml");
+        write_file(&legacy, "[[logs]]\ndirectory = \"/var/log\"\n");
+        write_file(&user, "");
+        let location = ConfigLocation {
+            path: user.clone(),
+            source: ConfigSource::Xdg,
+        };
+
+        let start_dir = home.path().join("projects/app");
+        fs::create_dir_all(&start_dir).unwrap();
+        let layers = discover_from(
+            &location,
+            Path::new("/missing"),
+            Some(&legacy),
+            Some(&start_dir),
+            false,
+        );
+        assert_eq!(layers, vec![ConfigLayer::new(user, LayerKind::User)]);
+    }
+
+    #[test]
+    fn test_later_layers_replace_groups_and_resolve_relative_paths() {
+        let root = tempdir().unwrap();
+        let user = root.path().join("user.toml");
+        let project = root.path().join("repo/.nuke.toml");
+        write_file(
+            &user,
+            "[categories]\nlogs = [\"log\"]\ndocs = [\"md\"]\n\n\
+             [[build]]\ndirectory = \"/old/build\"\n\n\
+             [[downloads]]\ndirectory = \"/home/downloads\"\n",
+        );
+        write_file(
+            &project,
+            "[categories]\nlogs = [\"log\", \"out\"]\n\n\
+             [[build]]\ndirectory = \"target\"\nextensions_to_delete = [\"o\"]\n",
+        );
+        let layers = vec![
+            ConfigLayer::new(user.clone(), LayerKind::User),
+            ConfigLayer {
+                may_override: true,
+                ..ConfigLayer::new(project, LayerKind::Project)
+            },
+        ];
+
+        let merged = load_layered_config(&layers, &user).unwrap();
+        let groups = merged.resolve_groups().unwrap().unwrap();
+        assert_eq!(groups["build"].len(), 1);
+        assert_eq!(
+            groups["build"][0].directory,
+            root.path().join("repo/target")
+        );
+        assert_eq!(
+            groups["downloads"][0].directory,
+            PathBuf::from("/home/downloads")
+        );
+
+        let categories = merged.categories.unwrap();
+        assert_eq!(categories["logs"], vec!["log", "out"]);
+        assert_eq!(categories["docs"], vec!["md"]);
+    }
+
+    #[test]
+    fn test_project_layers_may_not_replace_user_groups_unless_allowed() {
+        let root = tempdir().unwrap();
+        let user = root.path().join("user.toml");
+        let project = root.path().join("repo/.nuke.toml");
+        write_file(&user, "[[build]]\ndirectory = \"/old/build\"\n");
+        write_file(
+            &project,
+            "[[build]]\ndirectory = \"target\"\n\n[[cache]]\ndirectory = \".cache\"\n",
+        );
+        let layers = vec![
+            ConfigLayer::new(user.clone(), LayerKind::User),
+            ConfigLayer::new(project.clone(), LayerKind::Project),
+        ];
+
+        let result = load_layered_config(&layers, &user);
+        assert!(
+            matches!(result, Err(ConfigError::ProjectOverride(name, path)) if name == "build" && path == project)
+        );
+
+        // New groups are fine, only replacing existing ones needs the opt-in
+        write_file(&project, "[[cache]]\ndirectory = \".cache\"\n");
+        assert!(load_layered_config(&layers, &user).is_ok());
+    }
+
+    #[test]
+    fn test_project_layers_may_not_point_outside_the_project() {
+        let root = tempdir().unwrap();
+        let user = root.path().join("user.toml");
+        let project = root.path().join("repo/.nuke.toml");
+        write_file(&user, "");
+        let layers = vec![
+            ConfigLayer::new(user.clone(), LayerKind::User),
+            ConfigLayer::new(project.clone(), LayerKind::Project),
+        ];
+
+        for directory in ["../elsewhere", "/etc", "~/Documents", "$HOME/Documents"] {
+            write_file(
+                &project,
+                &format!("[[build]]\ndirectory = \"{}\"\n", directory),
+            );
+            let result = load_layered_config(&layers, &user);
+            assert!(
+                matches!(result, Err(ConfigError::OutsideProject(_, _))),
+                "{} was allowed",
+                directory
+            );
+        }
+
+        write_file(&project, "[[build]]\ndirectory = \"./target/../out\"\n");
+        assert!(load_layered_config(&layers, &user).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_project_layers_may_not_follow_symlinks_out_of_the_project() {
+        let root = tempdir().unwrap();
+        let user = root.path().join("user.toml");
+        let project = root.path().join("repo/.nuke.toml");
+        write_file(&user, "");
+        write_file(&project, "[[build]]\ndirectory = \"escape\"\n");
+        fs::create_dir(root.path().join("outside")).unwrap();
+        std::os::unix::fs::symlink(root.path().join("outside"), root.path().join("repo/escape"))
+            .unwrap();
+        let layers = vec![
+            ConfigLayer::new(user.clone(), LayerKind::User),
+            ConfigLayer::new(project, LayerKind::Project),
+        ];
+
+        let result = load_layered_config(&layers, &user);
+        assert!(matches!(result, Err(ConfigError::OutsideProject(_, _))));
+    }
+
+    #[test]
+    fn test_no_layers_reports_the_user_config_as_missing() {
+        let result = load_layered_config(&[], Path::new("/home/.nuke.toml"));
+        assert!(matches!(result, Err(ConfigError::ReadError(_))));
+    }
+}
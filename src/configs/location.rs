@@ -0,0 +1,219 @@
+use super::errors::ConfigError;
+use super::{get_user_config_path, get_user_home_dir, CONFIG_FILE_NAME};
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Points the tool at a config file, ahead of the XDG and legacy locations.
+pub const CONFIG_ENV_VAR: &str = "FOLDER_CLEANER_CONFIG";
+const XDG_DIRECTORY_NAME: &str = "folder_cleaner";
+const XDG_FILE_NAME: &str = "config.toml";
+// Configs generated by other tools may be written as YAML or JSON instead.
+const XDG_ALTERNATIVE_FILE_NAMES: [&str; 3] = ["config.yaml", "config.yml", "config.json"];
+
+/// Where the config file in use was found, in the order the locations are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    // Given with `--config`
+    CommandLine,
+    // Given through `$FOLDER_CLEANER_CONFIG`
+    EnvVar,
+    // `$XDG_CONFIG_HOME/folder_cleaner/config.toml` (or `.yaml`, `.yml`, `.json`), or
+    // `~/.config` if that's unset
+    Xdg,
+    // `~/.nuke.toml`
+    Legacy,
+    // No config file exists yet, so this is where one would be created
+    Default,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::CommandLine => write!(f, "given with --config"),
+            ConfigSource::EnvVar => write!(f, "given by ${}", CONFIG_ENV_VAR),
+            ConfigSource::Xdg => write!(f, "found in the XDG config directory"),
+            ConfigSource::Legacy => write!(f, "found at the legacy location"),
+            ConfigSource::Default => write!(
+                f,
+                "no config file exists yet, so this is where one will be created"
+            ),
+        }
+    }
+}
+
+/// The config file to use, and why it was picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLocation {
+    pub path: PathBuf,
+    pub source: ConfigSource,
+}
+
+impl ConfigLocation {
+    fn new(path: PathBuf, source: ConfigSource) -> ConfigLocation {
+        ConfigLocation { path, source }
+    }
+}
+
+// The values the lookup depends on, gathered up front so it can be tested
+// without touching the real environment.
+struct LookupInputs {
+    cli_path: Option<PathBuf>,
+    env_path: Option<OsString>,
+    xdg_config_home: Option<OsString>,
+    home_dir: Option<PathBuf>,
+    legacy_path: Option<PathBuf>,
+}
+
+fn non_empty(value: Option<OsString>) -> Option<OsString> {
+    value.filter(|value| !value.is_empty())
+}
+
+fn xdg_config_path(inputs: &LookupInputs) -> Option<PathBuf> {
+    // Relative values are invalid according to the XDG spec, so they're ignored.
+    let config_home = non_empty(inputs.xdg_config_home.clone())
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| inputs.home_dir.as_ref().map(|home| home.join(".config")))?;
+    Some(config_home.join(XDG_DIRECTORY_NAME).join(XDG_FILE_NAME))
+}
+
+fn resolve_from(inputs: LookupInputs) -> Result<ConfigLocation, ConfigError> {
+    // Explicitly chosen files are used even if they don't exist, so that
+    // mistakes are reported rather than silently falling back.
+    if let Some(path) = inputs.cli_path.clone() {
+        return Ok(ConfigLocation::new(path, ConfigSource::CommandLine));
+    }
+    if let Some(path) = non_empty(inputs.env_path.clone()) {
+        return Ok(ConfigLocation::new(path.into(), ConfigSource::EnvVar));
+    }
+
+    let xdg_path = xdg_config_path(&inputs);
+    let existing_xdg_path = xdg_path.as_ref().and_then(|path| {
+        let alternatives = XDG_ALTERNATIVE_FILE_NAMES
+            .iter()
+            .map(|name| path.with_file_name(name));
+        std::iter::once(path.clone())
+            .chain(alternatives)
+            .find(|path| path.is_file())
+    });
+    if let Some(path) = existing_xdg_path {
+        return Ok(ConfigLocation::new(path, ConfigSource::Xdg));
+    }
+    if let Some(path) = inputs.legacy_path.filter(|path| path.is_file()) {
+        return Ok(ConfigLocation::new(path, ConfigSource::Legacy));
+    }
+
+    xdg_path
+        .map(|path| ConfigLocation::new(path, ConfigSource::Default))
+        .ok_or(ConfigError::UserDirNotFound)
+}
+
+/// Works out which config file to use. The first of these wins:
+///
+/// 1. The file given with `--config`.
+/// 2. The file given by `$FOLDER_CLEANER_CONFIG`.
+/// 3. `$XDG_CONFIG_HOME/folder_cleaner/config.toml` (`~/.config` if it's unset), if it exists,
+///    or else `config.yaml`, `config.yml` or `config.json` in the same folder.
+/// 4. The legacy `~/.nuke.toml`, if it exists.
+///
+/// If none of the files exist, the XDG location is returned so new configs are created there.
+///
+/// # Arguments
+///
+/// * `cli_path` - The path given with `--config`, if any.
+pub fn resolve_config_path(cli_path: Option<&Path>) -> Result<ConfigLocation, ConfigError> {
+    resolve_from(LookupInputs {
+        cli_path: cli_path.map(Path::to_path_buf),
+        env_path: env::var_os(CONFIG_ENV_VAR),
+        xdg_config_home: env::var_os("XDG_CONFIG_HOME"),
+        home_dir: get_user_home_dir().ok(),
+        legacy_path: get_user_config_path(CONFIG_FILE_NAME).ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn inputs_for(home: &Path) -> LookupInputs {
+        LookupInputs {
+            cli_path: None,
+            env_path: None,
+            xdg_config_home: None,
+            home_dir: Some(home.to_path_buf()),
+            legacy_path: Some(home.join(CONFIG_FILE_NAME)),
+        }
+    }
+
+    #[test]
+    fn test_explicit_paths_win_even_if_missing() {
+        let home = tempdir().unwrap();
+        let mut inputs = inputs_for(home.path());
+        inputs.env_path = Some(OsString::from("/team/env.toml"));
+        inputs.cli_path = Some(PathBuf::from("/team/cli.toml"));
+
+        let location = resolve_from(inputs).unwrap();
+        assert_eq!(location.path, PathBuf::from("/team/cli.toml"));
+        assert_eq!(location.source, ConfigSource::CommandLine);
+
+        let mut inputs = inputs_for(home.path());
+        inputs.env_path = Some(OsString::from("/team/env.toml"));
+        assert_eq!(resolve_from(inputs).unwrap().source, ConfigSource::EnvVar);
+    }
+
+    #[test]
+    fn test_xdg_preferred_over_legacy_file() {
+        let home = tempdir().unwrap();
+        let legacy_path = home.path().join(CONFIG_FILE_NAME);
+        fs::write(&legacy_path, "").unwrap();
+
+        let location = resolve_from(inputs_for(home.path())).unwrap();
+        assert_eq!(
+            location,
+            ConfigLocation::new(legacy_path, ConfigSource::Legacy)
+        );
+
+        let xdg_home = home.path().join("xdg");
+        let xdg_path = xdg_home.join(XDG_DIRECTORY_NAME).join(XDG_FILE_NAME);
+        fs::create_dir_all(xdg_path.parent().unwrap()).unwrap();
+        fs::write(&xdg_path, "").unwrap();
+
+        let yaml_path = xdg_path.with_file_name("config.yaml");
+        fs::write(&yaml_path, "").unwrap();
+
+        let mut inputs = inputs_for(home.path());
+        inputs.xdg_config_home = Some(xdg_home.clone().into_os_string());
+        let location = resolve_from(inputs).unwrap();
+        assert_eq!(
+            location,
+            ConfigLocation::new(xdg_path.clone(), ConfigSource::Xdg)
+        );
+
+        // Other formats are only used when there's no `config.toml`.
+        fs::remove_file(&xdg_path).unwrap();
+        let mut inputs = inputs_for(home.path());
+        inputs.xdg_config_home = Some(xdg_home.into_os_string());
+        let location = resolve_from(inputs).unwrap();
+        assert_eq!(location, ConfigLocation::new(yaml_path, ConfigSource::Xdg));
+    }
+
+    #[test]
+    fn test_defaults_to_xdg_location_when_nothing_exists() {
+        let home = tempdir().unwrap();
+        let mut inputs = inputs_for(home.path());
+        // Relative XDG directories are ignored.
+        inputs.xdg_config_home = Some(OsString::from("relative/config"));
+
+        let location = resolve_from(inputs).unwrap();
+        assert_eq!(location.source, ConfigSource::Default);
+        assert_eq!(
+            location.path,
+            home.path().join(".config/folder_cleaner/config.toml")
+        );
+    }
+}
@@ -1,14 +1,30 @@
+pub mod categories;
 pub mod config;
+pub mod editing;
 pub mod errors;
-mod parsing;
+pub mod expansion;
+pub mod layers;
+pub mod location;
+pub mod parsing;
+pub mod presets;
 pub mod unwrap_config_groups;
+pub mod validation;
 
 use directories::UserDirs;
 use errors::ConfigError;
+use layers::ConfigLayer;
+use location::ConfigLocation;
 use std::path::PathBuf;
 
+/// The name of the legacy config file, kept in the user's home directory.
 pub const CONFIG_FILE_NAME: &str = ".nuke.toml";
 
+/// The user's home directory, located in a platform-independent manner.
+pub fn get_user_home_dir() -> Result<PathBuf, ConfigError> {
+    let user_dirs = UserDirs::new().ok_or(ConfigError::UserDirNotFound)?;
+    Ok(user_dirs.home_dir().to_path_buf())
+}
+
 /// Retrieves the file path for a user's configuration file.
 ///
 /// This function constructs the path to a user-specific configuration file
@@ -32,20 +48,23 @@ pub const CONFIG_FILE_NAME: &str = ".nuke.toml";
 /// println!("Config path: {:?}", config_path);
 /// ```
 pub fn get_user_config_path(config_filename: &str) -> Result<PathBuf, ConfigError> {
-    let user_dirs = UserDirs::new().ok_or(ConfigError::UserDirNotFound)?;
-    let user_dir = user_dirs.home_dir();
-    let config_file_path = user_dir.join(config_filename);
+    let config_file_path = get_user_home_dir()?.join(config_filename);
 
     Ok(config_file_path)
 }
 
-pub fn report_user_config_path(config_filename: &str) {
-    match get_user_config_path(config_filename) {
-        Ok(path) => println!(
-            "The path to your current configuration file is: {:?}. \
-            You can edit this file to customize your cleaning preferences.",
-            path
-        ),
-        Err(e) => eprintln!("Unable to locate the configuration file. Error: {}", e),
+/// Tells the user which config file is in use and why that one was picked, along with
+/// every file merged into the configuration.
+pub fn report_user_config_path(location: &ConfigLocation, layers: &[ConfigLayer]) {
+    println!(
+        "The path to your current configuration file is: {:?} ({}). \
+        You can edit this file to customize your cleaning preferences.",
+        location.path, location.source
+    );
+    if layers.iter().any(|layer| layer.path != location.path) {
+        println!("Configuration is merged from these files, later ones taking priority:");
+        for layer in layers {
+            println!("  {:<8} {:?}", layer.kind, layer.path);
+        }
     }
 }
@@ -1,26 +1,263 @@
-use super::config::{Config, PathConfigMap};
+use super::config::Config;
 use super::errors::ConfigError;
 
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
-use toml;
-use toml::de::Error;
+use std::path::{Path, PathBuf};
+
+/// The formats a config file can be written in, picked by the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// The format of a config file. Files with any other extension, such as the
+    /// legacy `~/.nuke.toml`, are read as TOML.
+    pub fn from_path(path: &Path) -> ConfigFormat {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFormat::Toml => write!(f, "TOML"),
+            ConfigFormat::Yaml => write!(f, "YAML"),
+            ConfigFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+/// Why a config couldn't be parsed, with where the parser gave up if it could tell.
+#[derive(Debug)]
+pub struct ConfigParseError {
+    pub format: ConfigFormat,
+    pub message: String,
+    // Zero based line and column
+    pub position: Option<(usize, usize)>,
+}
+
+impl ConfigParseError {
+    // Each parser adds " at line X column Y" to its messages, which is dropped in
+    // favour of the position it gives separately.
+    fn new(format: ConfigFormat, message: String, position: Option<(usize, usize)>) -> Self {
+        let message = match (position, message.rfind(" at line ")) {
+            (Some(_), Some(index)) => message[..index].to_string(),
+            _ => message,
+        };
+        ConfigParseError {
+            format,
+            message,
+            position,
+        }
+    }
+}
+
+// The name of the key in an "unknown field `name`, expected ..." message. YAML errors
+// lead with the path to the offending value, e.g. "logs[0]: unknown field `name`".
+fn unknown_field_name(message: &str) -> Option<&str> {
+    let start = message.find("unknown field `")?;
+    let rest = &message[start + "unknown field `".len()..];
+    rest.split('`').next()
+}
+
+impl ConfigParseError {
+    /// The misspelt key, for errors caused by a key the config doesn't know.
+    pub fn unknown_field(&self) -> Option<&str> {
+        unknown_field_name(&self.message)
+    }
+}
+
+impl From<toml::de::Error> for ConfigParseError {
+    fn from(error: toml::de::Error) -> Self {
+        ConfigParseError::new(ConfigFormat::Toml, error.to_string(), error.line_col())
+    }
+}
+
+impl From<serde_yaml::Error> for ConfigParseError {
+    fn from(error: serde_yaml::Error) -> Self {
+        let position = error
+            .location()
+            .map(|location| (location.line() - 1, location.column() - 1));
+        ConfigParseError::new(ConfigFormat::Yaml, error.to_string(), position)
+    }
+}
+
+impl From<serde_json::Error> for ConfigParseError {
+    fn from(error: serde_json::Error) -> Self {
+        // serde_json reports line 0 when an error isn't tied to a position.
+        let position =
+            (error.line() > 0).then(|| (error.line() - 1, error.column().saturating_sub(1)));
+        ConfigParseError::new(ConfigFormat::Json, error.to_string(), position)
+    }
+}
+
+impl fmt::Display for ConfigParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(
+                f,
+                "{} error at line {}, column {}: {}",
+                self.format,
+                line + 1,
+                column + 1,
+                self.message
+            ),
+            None => write!(f, "{} error: {}", self.format, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigParseError {}
 
 fn read_config_file(config_file_path: &PathBuf) -> Result<String, ConfigError> {
     fs::read_to_string(config_file_path).map_err(|_| ConfigError::read_error(config_file_path))
 }
 
-fn parse_config_from_str(file_content: &str) -> Result<Option<PathConfigMap>, Error> {
-    let config: Config = toml::from_str(file_content)?;
-    Ok(config.subgroups)
+fn parse_from_str<T>(file_content: &str, format: ConfigFormat) -> Result<T, ConfigParseError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    match format {
+        ConfigFormat::Toml => toml::from_str(file_content).map_err(ConfigParseError::from),
+        ConfigFormat::Yaml => serde_yaml::from_str(file_content).map_err(ConfigParseError::from),
+        ConfigFormat::Json => serde_json::from_str(file_content).map_err(ConfigParseError::from),
+    }
+}
+
+// Top-level tables that hold settings rather than a group of folders.
+const RESERVED_GROUP_NAMES: [&str; 3] = ["defaults", "profiles", "categories"];
+
+/// Whether `name` is taken by one of the config's own tables, so can't name a group.
+pub fn is_reserved_group_name(name: &str) -> bool {
+    RESERVED_GROUP_NAMES.contains(&name)
+}
+
+// `defaults` and `profiles` could once be used as group names. Configs that still do
+// would otherwise fail with an error about the settings those tables now hold.
+fn reserved_group_name(file_content: &str, format: ConfigFormat) -> Option<&'static str> {
+    let raw = parse_raw_config_from_str(file_content, format).ok()?;
+    let is_group = |value: &toml::Value| match value {
+        toml::Value::Array(_) => true,
+        toml::Value::Table(table) => {
+            table.contains_key("folders") || table.contains_key("directory")
+        }
+        _ => false,
+    };
+    RESERVED_GROUP_NAMES
+        .into_iter()
+        .find(|name| raw.get(name).is_some_and(is_group))
+}
+
+/// Parses a config written in the given format.
+pub fn parse_config_from_str(
+    file_content: &str,
+    format: ConfigFormat,
+) -> Result<Config, ConfigParseError> {
+    parse_from_str(file_content, format).map_err(|error| {
+        match reserved_group_name(file_content, format) {
+            Some(name) => ConfigParseError::new(
+                format,
+                format!(
+                    "`{}` is a reserved table name and can't be used for a group anymore. \
+                     Rename the group, e.g. to `my-{}`",
+                    name, name
+                ),
+                None,
+            ),
+            None => error,
+        }
+    })
+}
+
+// Turns a parsed value into a TOML value, leaving out keys set to `null`, which
+// TOML has no word for.
+fn toml_without_nulls(value: serde_json::Value) -> Result<toml::Value, toml::ser::Error> {
+    fn strip_nulls(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                map.retain(|_, value| !value.is_null());
+                map.values_mut().for_each(strip_nulls);
+            }
+            serde_json::Value::Array(values) => values.iter_mut().for_each(strip_nulls),
+            _ => {}
+        }
+    }
+    let mut value = value;
+    strip_nulls(&mut value);
+    toml::Value::try_from(value)
+}
+
+/// Parses a config without checking it against the config's fields, so it can be
+/// inspected or converted as it was written. Every format is read into TOML values,
+/// with keys set to `null` left out.
+pub fn parse_raw_config_from_str(
+    file_content: &str,
+    format: ConfigFormat,
+) -> Result<toml::Value, ConfigParseError> {
+    match format {
+        ConfigFormat::Toml => parse_from_str(file_content, format),
+        _ => toml_without_nulls(parse_from_str(file_content, format)?)
+            .map_err(|error| ConfigParseError::new(format, error.to_string(), None)),
+    }
+}
+
+/// Rewrites a config in another format. The config is checked before it's converted,
+/// so a broken config is reported rather than carried over. Comments aren't kept.
+///
+/// # Arguments
+///
+/// * `file_content` - The config to convert.
+/// * `from` - The format `file_content` is written in.
+/// * `to` - The format to convert it to.
+pub fn convert_config(
+    file_content: &str,
+    from: ConfigFormat,
+    to: ConfigFormat,
+) -> Result<String, ConfigParseError> {
+    parse_config_from_str(file_content, from)?;
+    let raw = parse_raw_config_from_str(file_content, from)?;
+
+    // TOML needs plain values ahead of tables, which its own values take care of.
+    // The other formats keep keys in name order, as they're read.
+    let converted = match to {
+        ConfigFormat::Toml => toml::to_string_pretty(&raw).map_err(|e| e.to_string()),
+        format => serde_json::to_value(&raw)
+            .and_then(|value| match format {
+                ConfigFormat::Yaml => {
+                    serde_yaml::to_string(&value).map_err(serde::ser::Error::custom)
+                }
+                _ => serde_json::to_string_pretty(&value).map(|json| json + "\n"),
+            })
+            .map_err(|e| e.to_string()),
+    };
+    converted.map_err(|message| ConfigParseError::new(to, message, None))
 }
 
 /// Extracts the user configuration from a specified configuration file.
 ///
-/// This function attempts to locate and parse a user-specific configuration file,
-/// returning the configuration settings it contains. It first constructs the file path
-/// using `get_user_config_path` with the provided `config_filename`, then parses
-/// the configuration file into structured data.
+/// The file is parsed as TOML, YAML or JSON depending on its extension, see
+/// [`ConfigFormat::from_path`].
 ///
 /// # Arguments
 ///
@@ -29,7 +266,7 @@ fn parse_config_from_str(file_content: &str) -> Result<Option<PathConfigMap>, Er
 /// # Returns
 ///
 /// This function returns a `Result` which is either:
-/// - `Ok((SizeConfig, Option<PathConfigMap>))` containing the parsed configuration data if successful.
+/// - `Ok(Config)` containing the parsed configuration data if successful.
 /// - `Err(ConfigError)` describing the type of error encountered (e.g., file not found, parse error).
 ///
 /// # Examples
@@ -38,13 +275,13 @@ fn parse_config_from_str(file_content: &str) -> Result<Option<PathConfigMap>, Er
 /// let user_config = extract_user_config_from_path("app_config.toml")
 ///     .expect("Failed to extract user config");
 /// ```
-pub fn extract_user_config_from_path(
-    config_file_path: &PathBuf,
-) -> Result<Option<PathConfigMap>, ConfigError> {
-    let file_content = read_config_file(&config_file_path)
-        .map_err(|_| ConfigError::read_error(&config_file_path))?;
+pub fn extract_user_config_from_path(config_file_path: &PathBuf) -> Result<Config, ConfigError> {
+    let file_content = read_config_file(config_file_path)
+        .map_err(|_| ConfigError::read_error(config_file_path))?;
 
-    parse_config_from_str(&file_content).map_err(|e| ConfigError::parse_error(&config_file_path, e))
+    let format = ConfigFormat::from_path(config_file_path);
+    parse_config_from_str(&file_content, format)
+        .map_err(|e| ConfigError::parse_error(config_file_path, e))
 }
 
 #[cfg(test)]
@@ -87,7 +324,11 @@ mod tests {
             extensions_to_keep = ["xlsx", "pptx"]
         "#;
 
-        let subgroups = parse_config_from_str(toml_str).unwrap().unwrap();
+        let subgroups = parse_config_from_str(toml_str, ConfigFormat::Toml)
+            .unwrap()
+            .resolve_groups()
+            .unwrap()
+            .unwrap();
         assert_eq!(subgroups.len(), 2);
 
         // Check the downloads group
@@ -130,7 +371,10 @@ mod tests {
             directory = "/example/images"
         "#;
 
-        let subgroups = parse_config_from_str(toml_str).unwrap();
+        let subgroups = parse_config_from_str(toml_str, ConfigFormat::Toml)
+            .unwrap()
+            .resolve_groups()
+            .unwrap();
 
         // Ensure that subgroups is not None and contains "images"
         assert!(subgroups.is_some(), "Subgroups should be Some");
@@ -159,6 +403,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_categories_alongside_subgroups() {
+        let toml_str = r#"
+            [categories]
+            images = ["psd"]
+
+            [[images]]
+            directory = "/example/images"
+        "#;
+
+        let config = parse_config_from_str(toml_str, ConfigFormat::Toml).unwrap();
+        let categories = config.categories.unwrap();
+        assert_eq!(categories["images"], vec!["psd".to_string()]);
+
+        let subgroups = config.subgroups.unwrap();
+        assert_eq!(subgroups.len(), 1);
+        assert!(subgroups.contains_key("images"));
+    }
+
     #[test]
     fn test_load_and_parse_invalid_toml() {
         let invalid_toml = r#"
@@ -194,6 +457,159 @@ mod tests {
         }
     }
 
+    const TOML_CONFIG: &str = r#"
+        [defaults]
+        older_than_days = 30
+
+        [[downloads]]
+        directory = "/example/downloads"
+        extensions_to_delete = ["tmp", ".log"]
+
+        [logs.defaults]
+        recursive = true
+
+        [[logs.folders]]
+        directory = "/var/log/app"
+    "#;
+
+    #[test]
+    fn test_yaml_and_json_parse_like_toml() {
+        let yaml = "defaults:\n  older_than_days: 30\n\
+                    downloads:\n  - directory: /example/downloads\n    extensions_to_delete: [tmp, .log]\n\
+                    logs:\n  defaults:\n    recursive: true\n  folders:\n    - directory: /var/log/app\n";
+        let json = r#"{
+            "defaults": {"older_than_days": 30},
+            "downloads": [{"directory": "/example/downloads", "extensions_to_delete": ["tmp", ".log"]}],
+            "logs": {"defaults": {"recursive": true}, "folders": [{"directory": "/var/log/app"}]}
+        }"#;
+
+        let resolve = |content: &str, format| {
+            parse_config_from_str(content, format)
+                .unwrap()
+                .resolve_groups()
+                .unwrap()
+                .unwrap()
+        };
+        let expected = resolve(TOML_CONFIG, ConfigFormat::Toml);
+        for groups in [
+            resolve(yaml, ConfigFormat::Yaml),
+            resolve(json, ConfigFormat::Json),
+        ] {
+            assert_eq!(
+                groups["downloads"][0].extensions_to_delete,
+                expected["downloads"][0].extensions_to_delete
+            );
+            assert_eq!(groups["downloads"][0].older_than_days, Some(30));
+            assert_eq!(groups["logs"][0].directory, PathBuf::from("/var/log/app"));
+            assert!(groups["logs"][0].recursive);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_give_the_format_and_position() {
+        let error = parse_config_from_str("{\n  \"logs\": [\n", ConfigFormat::Json).unwrap_err();
+        assert_eq!(error.format, ConfigFormat::Json);
+        assert_eq!(error.position.map(|(line, _)| line), Some(2));
+        assert!(error.to_string().starts_with("JSON error at line 3"));
+
+        let error =
+            parse_config_from_str("logs:\n  - directory: [\n", ConfigFormat::Yaml).unwrap_err();
+        assert!(error.to_string().starts_with("YAML error at line"));
+    }
+
+    #[test]
+    fn test_yaml_and_json_type_errors_point_at_the_value() {
+        let yaml = "downloads:\n  - directory: /tmp\n    recursive: \"yes\"\n";
+        let json = "{\n  \"downloads\": [\n    {\n      \"directory\": \"/tmp\",\n      \
+                    \"recursive\": \"yes\"\n    }\n  ]\n}\n";
+        for (content, format, position) in [
+            (yaml, ConfigFormat::Yaml, (2, 15)),
+            (json, ConfigFormat::Json, (4, 23)),
+        ] {
+            let error = parse_config_from_str(content, format).unwrap_err();
+            assert!(error.message.contains("expected a boolean"));
+            assert_eq!(error.position, Some(position));
+        }
+        let error = parse_config_from_str(yaml, ConfigFormat::Yaml).unwrap_err();
+        assert!(error.message.starts_with("downloads[0].recursive:"));
+    }
+
+    #[test]
+    fn test_errors_inside_groups_name_the_group() {
+        let content = "[[logs]]\ndirectory = \"/tmp\"\ntags = [\"a\", 5]\n";
+        let error = parse_config_from_str(content, ConfigFormat::Toml).unwrap_err();
+        assert!(error.message.contains("for key `logs.tags`"));
+
+        let content = "[logs]\nfolders = [{ directory = \"/tmp\", recursive = 5 }]\n";
+        let error = parse_config_from_str(content, ConfigFormat::Toml).unwrap_err();
+        assert!(error.message.contains("for key `logs.folders`"));
+    }
+
+    #[test]
+    fn test_yaml_and_json_nulls_are_unset() {
+        let yaml = "downloads:\n  - directory: /tmp\n    older_than_days: null\n";
+        let json = r#"{"downloads": [{"directory": "/tmp", "older_than_days": null}]}"#;
+        for (content, format) in [(yaml, ConfigFormat::Yaml), (json, ConfigFormat::Json)] {
+            let groups = parse_config_from_str(content, format)
+                .unwrap()
+                .resolve_groups()
+                .unwrap()
+                .unwrap();
+            assert_eq!(groups["downloads"][0].older_than_days, None);
+
+            let toml = convert_config(content, format, ConfigFormat::Toml).unwrap();
+            assert!(!toml.contains("older_than_days"));
+        }
+    }
+
+    #[test]
+    fn test_reserved_names_used_as_groups_are_reported() {
+        for (content, name) in [
+            ("[[defaults]]\ndirectory = \"/tmp\"\n", "defaults"),
+            (
+                "[profiles]\nfolders = [{ directory = \"/tmp\" }]\n",
+                "profiles",
+            ),
+        ] {
+            let error = parse_config_from_str(content, ConfigFormat::Toml).unwrap_err();
+            assert!(error
+                .message
+                .starts_with(&format!("`{}` is a reserved", name)));
+        }
+        let error = parse_config_from_str("defaults:\n  - directory: /tmp\n", ConfigFormat::Yaml)
+            .unwrap_err();
+        assert!(error.message.contains("`defaults` is a reserved"));
+    }
+
+    #[test]
+    fn test_convert_round_trips_between_formats() {
+        let yaml = convert_config(TOML_CONFIG, ConfigFormat::Toml, ConfigFormat::Yaml).unwrap();
+        let json = convert_config(&yaml, ConfigFormat::Yaml, ConfigFormat::Json).unwrap();
+        let toml = convert_config(&json, ConfigFormat::Json, ConfigFormat::Toml).unwrap();
+
+        assert_eq!(
+            parse_raw_config_from_str(&toml, ConfigFormat::Toml).unwrap(),
+            parse_raw_config_from_str(TOML_CONFIG, ConfigFormat::Toml).unwrap()
+        );
+        assert!(convert_config("typo = 1\n", ConfigFormat::Toml, ConfigFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_format_is_picked_by_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.YML")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("fleet.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new(".nuke.toml")),
+            ConfigFormat::Toml
+        );
+    }
+
     // TODO: Extend tests to cover more cases!
     // - Test all of our potential sources of error while deserialising:
     // - Test that the function returns the correct error when the file is not found
@@ -0,0 +1,212 @@
+use super::config::{FolderSettings, PathConfig};
+
+/// A ready-made set of filters for a common kind of clutter. Presets can be used
+/// from the config with `profile = "<name>"`, or with `--preset <name>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    // Where this kind of clutter usually lives, relative to the home directory
+    pub suggested_directory: Option<&'static str>,
+    pub extensions_to_delete: &'static [&'static str],
+    // File or folder names, which may be globs, e.g. `*~` or `__pycache__`
+    pub names_to_delete: &'static [&'static str],
+    pub older_than_days: Option<u64>,
+    pub recursive: bool,
+    pub delete_hidden: bool,
+}
+
+pub const PRESETS: [Preset; 11] = [
+    Preset {
+        name: "downloads",
+        description: "Installers, archives and unfinished downloads",
+        suggested_directory: Some("Downloads"),
+        extensions_to_delete: &[
+            "dmg",
+            "pkg",
+            "msi",
+            "exe",
+            "deb",
+            "rpm",
+            "zip",
+            "tar",
+            "gz",
+            "part",
+            "crdownload",
+        ],
+        names_to_delete: &[],
+        older_than_days: Some(30),
+        recursive: false,
+        delete_hidden: false,
+    },
+    Preset {
+        name: "partial-downloads",
+        description: "Downloads that were cancelled or never finished",
+        suggested_directory: Some("Downloads"),
+        extensions_to_delete: &["part", "partial", "crdownload", "opdownload"],
+        // Safari keeps unfinished downloads in `<name>.download` folders
+        names_to_delete: &["*.download"],
+        older_than_days: Some(2),
+        recursive: false,
+        delete_hidden: false,
+    },
+    Preset {
+        name: "logs",
+        description: "Log files and captured output",
+        suggested_directory: None,
+        extensions_to_delete: &["log", "out", "err", "trace"],
+        names_to_delete: &[],
+        older_than_days: Some(14),
+        recursive: false,
+        delete_hidden: false,
+    },
+    Preset {
+        name: "build",
+        description: "Compiled objects and other build artefacts",
+        suggested_directory: None,
+        extensions_to_delete: &[
+            "o", "obj", "a", "so", "class", "pyc", "pyo", "rlib", "rmeta",
+        ],
+        names_to_delete: &[],
+        older_than_days: None,
+        recursive: false,
+        delete_hidden: false,
+    },
+    Preset {
+        name: "editor-backups",
+        description: "Editor swap, backup and merge leftovers",
+        suggested_directory: None,
+        extensions_to_delete: &["swp", "swo", "swn", "bak", "orig", "rej"],
+        // Backups like `notes.txt~` and Emacs autosaves like `#notes.txt#`
+        names_to_delete: &["*~", "#*#"],
+        older_than_days: Some(7),
+        recursive: true,
+        // Swap files are usually hidden, e.g. `.notes.txt.swp`
+        delete_hidden: true,
+    },
+    Preset {
+        name: "os-cruft",
+        description: "Folder metadata left behind by macOS and Windows",
+        suggested_directory: None,
+        extensions_to_delete: &[],
+        names_to_delete: &[
+            ".DS_Store",
+            "._*",
+            ".AppleDouble",
+            "Thumbs.db",
+            "ehthumbs.db",
+            "desktop.ini",
+        ],
+        older_than_days: None,
+        recursive: true,
+        delete_hidden: true,
+    },
+    Preset {
+        name: "python-caches",
+        description: "Bytecode and tool caches from Python projects",
+        suggested_directory: None,
+        extensions_to_delete: &["pyc", "pyo"],
+        names_to_delete: &["__pycache__", ".pytest_cache", ".mypy_cache", ".ruff_cache"],
+        older_than_days: None,
+        recursive: true,
+        delete_hidden: true,
+    },
+    Preset {
+        name: "rust-build",
+        description: "Cargo `target` folders",
+        suggested_directory: None,
+        extensions_to_delete: &[],
+        names_to_delete: &["target"],
+        older_than_days: None,
+        recursive: true,
+        delete_hidden: true,
+    },
+    Preset {
+        name: "node-build",
+        description: "Installed packages and bundler caches from Node projects",
+        suggested_directory: None,
+        extensions_to_delete: &[],
+        names_to_delete: &["node_modules", ".next", ".parcel-cache", ".turbo"],
+        older_than_days: None,
+        recursive: true,
+        delete_hidden: true,
+    },
+    Preset {
+        name: "java-build",
+        description: "Class files and Gradle caches from Java projects",
+        suggested_directory: None,
+        extensions_to_delete: &["class"],
+        names_to_delete: &[".gradle"],
+        older_than_days: None,
+        recursive: true,
+        delete_hidden: true,
+    },
+    Preset {
+        name: "cache",
+        description: "Anything in the cache folder that hasn't changed in a month",
+        suggested_directory: Some(".cache"),
+        extensions_to_delete: &[],
+        names_to_delete: &["*"],
+        older_than_days: Some(30),
+        recursive: true,
+        delete_hidden: true,
+    },
+];
+
+fn to_strings(values: &[&str]) -> Option<Vec<String>> {
+    match values.is_empty() {
+        true => None,
+        false => Some(values.iter().map(|value| value.to_string()).collect()),
+    }
+}
+
+/// Finds the built in preset with the given name.
+pub fn find_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+impl Preset {
+    /// The preset as profile settings. Presets only ever switch on subfolders and
+    /// hidden files, leaving them to the group otherwise.
+    pub fn settings(&self) -> FolderSettings {
+        FolderSettings {
+            extensions_to_delete: to_strings(self.extensions_to_delete),
+            extensions_to_keep: None,
+            names_to_delete: to_strings(self.names_to_delete),
+            recursive: self.recursive.then_some(true),
+            delete_hidden: self.delete_hidden.then_some(true),
+            older_than_days: self.older_than_days,
+            tags: None,
+        }
+    }
+
+    /// Replaces the filters of `config` with the preset's. Subfolders and hidden files
+    /// are included if either the preset or `config` asks for them, and the longer of
+    /// the two age cutoffs is kept.
+    pub fn apply_to(&self, config: &mut PathConfig) {
+        let settings = self.settings();
+        config.extensions_to_delete = settings.extensions_to_delete;
+        config.names_to_delete = settings.names_to_delete;
+        // `None` sorts first, so a cutoff on either side always survives
+        config.older_than_days = config.older_than_days.max(settings.older_than_days);
+        config.recursive |= self.recursive;
+        config.delete_hidden |= self.delete_hidden;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_applying_a_preset_keeps_the_longer_age_cutoff() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.older_than_days = Some(10);
+        find_preset("rust-build").unwrap().apply_to(&mut config);
+        assert_eq!(config.older_than_days, Some(10));
+
+        find_preset("downloads").unwrap().apply_to(&mut config);
+        assert_eq!(config.older_than_days, Some(30));
+    }
+}
@@ -1,11 +1,29 @@
-use crate::configs::config::{PathConfig, PathConfigMap};
+use crate::configs::config::{Config, PathConfig, PathConfigMap};
 use crate::configs::errors::ConfigError;
-use crate::configs::parsing::extract_user_config_from_path;
+use crate::configs::expansion::expand_directory;
+use crate::configs::layers::{load_layered_config, ConfigLayer};
 
-use std::path::PathBuf;
+use glob::Pattern;
+use std::collections::HashMap;
+use std::fs::canonicalize;
+use std::path::{Path, PathBuf};
+
+// Tags every entry in a group with the name of the group it came from.
+fn label_group(name: &str, group: Vec<PathConfig>) -> Vec<PathConfig> {
+    group
+        .into_iter()
+        .map(|mut config| {
+            config.group = Some(name.to_string());
+            config
+        })
+        .collect()
+}
 
 fn unwrap_all_subgroups(subgroups: PathConfigMap) -> Vec<PathConfig> {
-    subgroups.into_iter().flat_map(|(_, group)| group).collect()
+    subgroups
+        .into_iter()
+        .flat_map(|(name, group)| label_group(&name, group))
+        .collect()
 }
 
 fn get_subgroup(subgroups: PathConfigMap, subgroup: &str) -> Result<Vec<PathConfig>, ConfigError> {
@@ -14,7 +32,7 @@ fn get_subgroup(subgroups: PathConfigMap, subgroup: &str) -> Result<Vec<PathConf
     let result = subgroups.into_iter().find(|(key, _)| key == subgroup);
 
     match result {
-        Some((_, group)) => Ok(group),
+        Some((name, group)) => Ok(label_group(&name, group)),
         None => Err(ConfigError::FolderMapEmpty(subgroup.to_string())),
     }
 }
@@ -29,22 +47,143 @@ fn get_path_config_folder_groups(
     }
 }
 
-pub fn fetch_cli_configs(
-    config_filepath: &PathBuf,
+/// Reads and merges every config layer, then returns the folders in the chosen group.
+/// Directories are left as they're written, see `expand_folders`.
+///
+/// # Arguments
+///
+/// * `layers` - The config files to merge, lowest priority first.
+/// * `user_config_path` - Reported as missing if there are no layers at all.
+/// * `filter_group` - The group to return, or every group if `None`.
+pub fn fetch_layered_configs(
+    layers: &[ConfigLayer],
+    user_config_path: &Path,
     filter_group: Option<&str>,
 ) -> Result<Vec<PathConfig>, ConfigError> {
-    let config_map = extract_user_config_from_path(config_filepath)?;
+    let config = load_layered_config(layers, user_config_path)?;
+    unwrap_config(config, user_config_path, filter_group)
+}
 
-    let config_map =
-        config_map.ok_or_else(|| ConfigError::ConfigNotFound(config_filepath.clone()))?;
+fn unwrap_config(
+    config: Config,
+    config_filepath: &Path,
+    filter_group: Option<&str>,
+) -> Result<Vec<PathConfig>, ConfigError> {
+    let config_map = config
+        .resolve_groups()?
+        .ok_or_else(|| ConfigError::ConfigNotFound(config_filepath.to_path_buf()))?;
 
-    let folder_configs = get_path_config_folder_groups(filter_group, config_map)?;
+    let mut folder_configs = get_path_config_folder_groups(filter_group, config_map)?;
+    for folder_config in folder_configs.iter_mut() {
+        folder_config.categories = config.categories.clone();
+    }
     Ok(folder_configs)
 }
 
+// A directory written as a glob gives one folder per match, each with the same settings.
+fn expand_folder(folder_config: &PathConfig) -> Result<Vec<PathConfig>, ConfigError> {
+    let directories = expand_directory(&folder_config.directory)?;
+    Ok(directories
+        .into_iter()
+        .map(|directory| PathConfig {
+            directory,
+            ..folder_config.clone()
+        })
+        .collect())
+}
+
+/// A folder from the config whose directory couldn't be expanded, e.g. a glob that
+/// no longer matches anything.
+#[derive(Debug)]
+pub struct UnexpandedFolder {
+    pub config: PathConfig,
+    pub error: ConfigError,
+}
+
+/// Expands `~`, environment variables and globs in the directory of each folder. Only
+/// the folders that are used need expanding, so a stale entry in one group doesn't
+/// stop any other group from being used. Folders that can't be expanded are returned
+/// alongside the rest, as they were written.
+pub fn expand_folders(configs: Vec<PathConfig>) -> (Vec<PathConfig>, Vec<UnexpandedFolder>) {
+    let mut expanded = Vec::new();
+    let mut unexpanded = Vec::new();
+    for config in configs {
+        match expand_folder(&config) {
+            Ok(folders) => expanded.extend(folders),
+            Err(error) => unexpanded.push(UnexpandedFolder { config, error }),
+        }
+    }
+    (expanded, unexpanded)
+}
+
+/// The folders in every group whose name matches `pattern`, which may be a glob
+/// such as `build-*`. Patterns that aren't valid globs only match a group of that name.
+pub fn configs_in_matching_groups(configs: &[PathConfig], pattern: &str) -> Vec<PathConfig> {
+    let glob = Pattern::new(pattern).ok();
+    configs
+        .iter()
+        .filter(|config| {
+            config.group.as_deref().is_some_and(|group| match &glob {
+                Some(glob) => glob.matches(group),
+                None => group == pattern,
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// The folders, from any group, labelled with `tag`.
+pub fn configs_with_tag(configs: &[PathConfig], tag: &str) -> Vec<PathConfig> {
+    configs
+        .iter()
+        .filter(|config| config.tags.iter().any(|config_tag| config_tag == tag))
+        .cloned()
+        .collect()
+}
+
+/// A folder that was selected again with different filters, e.g. from two groups.
+#[derive(Debug)]
+pub struct DuplicateFolder {
+    pub directory: PathBuf,
+    // The groups of the selection that's kept and the one that's dropped
+    pub kept_group: Option<String>,
+    pub dropped_group: Option<String>,
+}
+
+/// Drops any folder that's already been selected, so each directory is only
+/// scanned once. The first selection of a directory is the one kept, and any
+/// later selection with different filters is returned alongside the folders.
+pub fn dedupe_directories(configs: Vec<PathConfig>) -> (Vec<PathConfig>, Vec<DuplicateFolder>) {
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    let mut kept: Vec<PathConfig> = Vec::new();
+    let mut duplicates: Vec<DuplicateFolder> = Vec::new();
+
+    for config in configs {
+        let directory =
+            canonicalize(&config.directory).unwrap_or_else(|_| config.directory.clone());
+        match seen.get(&directory) {
+            Some(&index) if !kept[index].has_same_filters(&config) => {
+                duplicates.push(DuplicateFolder {
+                    directory,
+                    kept_group: kept[index].group.clone(),
+                    dropped_group: config.group,
+                });
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(directory, kept.len());
+                kept.push(config);
+            }
+        }
+    }
+
+    (kept, duplicates)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configs::layers::LayerKind;
     use std::fs::File;
     use std::io::Write;
     use tempfile::tempdir;
@@ -68,7 +207,8 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "{}", dummy_contents).unwrap();
 
-        let result = fetch_cli_configs(&file_path, filter_group);
+        let layers = [ConfigLayer::new(file_path.clone(), LayerKind::User)];
+        let result = fetch_layered_configs(&layers, &file_path, filter_group);
         if let Some(expected) = expected_len {
             if let Ok(folder_configs) = &result {
                 assert_eq!(
@@ -84,8 +224,9 @@ mod tests {
 
     #[test]
     fn test_fetch_cli_configs_valid_filter() {
-        test_config_fetch_helper(Some("core"), Some(1))
+        let configs = test_config_fetch_helper(Some("core"), Some(1))
             .expect("Expected valid fetch with 'core' filter");
+        assert_eq!(configs[0].group.as_deref(), Some("core"));
     }
 
     #[test]
@@ -93,6 +234,59 @@ mod tests {
         test_config_fetch_helper(None, Some(2)).expect("Expected valid fetch without filter");
     }
 
+    #[test]
+    fn test_fetch_cli_configs_cleans_extensions() {
+        let configs = test_config_fetch_helper(Some("downloads"), Some(1))
+            .expect("Expected valid fetch with 'downloads' filter");
+        assert_eq!(
+            configs[0].extensions_to_delete.as_ref().unwrap(),
+            &vec!["xlsx".to_string(), "rs".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_select_configs_by_glob_and_tag() {
+        let mut configs = test_config_fetch_helper(None, Some(2)).unwrap();
+        configs[1].tags = vec!["nightly".to_string()];
+
+        let matched = configs_in_matching_groups(&configs, "do*");
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].group.as_deref(), Some("downloads"));
+        assert!(configs_in_matching_groups(&configs, "[core").is_empty());
+
+        let tagged = configs_with_tag(&configs, "nightly");
+        assert_eq!(tagged[0].group.as_deref(), Some("downloads"));
+        assert!(configs_with_tag(&configs, "weekly").is_empty());
+    }
+
+    #[test]
+    fn test_dedupe_directories_keeps_first_selection() {
+        let mut configs = test_config_fetch_helper(None, Some(2)).unwrap();
+        let mut duplicate = configs[0].clone();
+        duplicate.group = Some("again".to_string());
+        configs.push(duplicate);
+
+        let (deduped, duplicates) = dedupe_directories(configs);
+        assert_eq!(deduped.len(), 2);
+        assert_ne!(deduped[1].group.as_deref(), Some("again"));
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_dedupe_directories_reports_different_filters() {
+        let mut configs = test_config_fetch_helper(Some("core"), Some(1)).unwrap();
+        let mut duplicate = configs[0].clone();
+        duplicate.group = Some("again".to_string());
+        duplicate.recursive = true;
+        configs.push(duplicate);
+
+        let (deduped, duplicates) = dedupe_directories(configs);
+        assert_eq!(deduped.len(), 1);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].kept_group.as_deref(), Some("core"));
+        assert_eq!(duplicates[0].dropped_group.as_deref(), Some("again"));
+    }
+
     #[test]
     fn test_fetch_cli_configs_invalid_filter() {
         let result = test_config_fetch_helper(Some("nonexistent"), None);
@@ -101,4 +295,59 @@ mod tests {
             "Expected error for nonexistent filter group"
         );
     }
+
+    #[test]
+    fn test_glob_directory_gives_a_folder_per_match() {
+        let root = tempdir().unwrap();
+        for folder in ["one/tmp", "two/tmp"] {
+            std::fs::create_dir_all(root.path().join(folder)).unwrap();
+        }
+        let file_path = root.path().join("config.toml");
+        std::fs::write(
+            &file_path,
+            format!(
+                "[[scratch]]\ndirectory = \"{}/*/tmp\"\nolder_than_days = 3\n",
+                root.path().display()
+            ),
+        )
+        .unwrap();
+
+        let layers = [ConfigLayer::new(file_path.clone(), LayerKind::User)];
+        let configs = fetch_layered_configs(&layers, &file_path, None).unwrap();
+        let (configs, unexpanded) = expand_folders(configs);
+        assert!(unexpanded.is_empty());
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].directory, root.path().join("two/tmp"));
+        assert!(configs
+            .iter()
+            .all(|config| config.group.as_deref() == Some("scratch")
+                && config.older_than_days == Some(3)));
+    }
+
+    #[test]
+    fn test_folders_that_cannot_be_expanded_are_set_aside() {
+        let root = tempdir().unwrap();
+        let file_path = root.path().join("config.toml");
+        std::fs::write(
+            &file_path,
+            format!(
+                "[[stale]]\ndirectory = \"{0}/*/missing\"\n\n[[work]]\ndirectory = \"{0}\"\n",
+                root.path().display()
+            ),
+        )
+        .unwrap();
+
+        let layers = [ConfigLayer::new(file_path.clone(), LayerKind::User)];
+        let configs = fetch_layered_configs(&layers, &file_path, None).unwrap();
+        assert_eq!(configs.len(), 2);
+
+        let (configs, unexpanded) = expand_folders(configs);
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].group.as_deref(), Some("work"));
+        assert_eq!(unexpanded.len(), 1);
+        assert!(matches!(
+            unexpanded[0].error,
+            ConfigError::UnmatchedDirectoryPattern(_)
+        ));
+    }
 }
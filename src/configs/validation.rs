@@ -0,0 +1,565 @@
+use super::config::{Config, PathConfig};
+use super::errors::ConfigError;
+use super::expansion::{expand_directory, is_anchored_elsewhere};
+use super::layers::{ConfigLayer, LayerKind};
+use super::parsing::{
+    parse_config_from_str, parse_raw_config_from_str, ConfigFormat, ConfigParseError,
+};
+
+use glob::Pattern;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, canonicalize};
+use std::path::{Path, PathBuf};
+
+// Cleaning any of these would take far more than the user meant to.
+const DANGEROUS_DIRECTORIES: [&str; 12] = [
+    "/bin",
+    "/boot",
+    "/dev",
+    "/etc",
+    "/lib",
+    "/proc",
+    "/sbin",
+    "/sys",
+    "/usr",
+    "/var",
+    "/System",
+    "/Applications",
+];
+const EXTENSION_KEYS: [&str; 2] = ["extensions_to_delete", "extensions_to_keep"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // The entry the problem was found in, e.g. `downloads #2`
+    pub location: Option<String>,
+    pub message: String,
+    // How to fix the problem, where there's an obvious fix
+    pub hint: Option<String>,
+    // The offending line of the file, for errors found while parsing
+    pub snippet: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, location: Option<String>, message: String) -> Diagnostic {
+        Diagnostic {
+            severity,
+            location,
+            message,
+            hint: None,
+            snippet: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: &str) -> Diagnostic {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "[{}] {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Everything that was found while checking one config file.
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+}
+
+// Shows the line an error was found on, with a caret under the column.
+fn source_snippet(content: &str, line: usize, column: usize) -> Option<String> {
+    let text = content.lines().nth(line)?;
+    let number = (line + 1).to_string();
+    let gutter = " ".repeat(number.len());
+    Some(format!(
+        "{} |\n{} | {}\n{} | {}^",
+        gutter,
+        number,
+        text,
+        gutter,
+        " ".repeat(column)
+    ))
+}
+
+fn parse_diagnostic(content: &str, error: &ConfigParseError) -> Diagnostic {
+    let message = match error.format {
+        ConfigFormat::Toml => error.message.clone(),
+        format => format!("{} error: {}", format, error.message),
+    };
+    let mut diagnostic = Diagnostic::new(Severity::Error, None, message);
+    if error.unknown_field().is_some() {
+        diagnostic = diagnostic.with_hint(
+            "check the spelling against the keys listed at the top of a file made by `config init`",
+        );
+    }
+    diagnostic.snippet = error
+        .position
+        .and_then(|(line, column)| source_snippet(content, line, column));
+    diagnostic
+}
+
+fn entry_label(group: &str, index: usize, group_size: usize) -> String {
+    match group_size {
+        1 => group.to_string(),
+        _ => format!("{} #{}", group, index + 1),
+    }
+}
+
+// Every table in the raw config that can hold folder settings, with where it was found.
+fn settings_tables(raw: &toml::Value) -> Vec<(String, &toml::Value)> {
+    let mut tables = Vec::new();
+    let Some(top_level) = raw.as_table() else {
+        return tables;
+    };
+    for (key, value) in top_level {
+        match (key.as_str(), value) {
+            ("categories", _) => {}
+            ("defaults", _) => tables.push(("defaults".to_string(), value)),
+            ("profiles", toml::Value::Table(profiles)) => tables.extend(
+                profiles
+                    .iter()
+                    .map(|(name, profile)| (format!("profiles.{}", name), profile)),
+            ),
+            (group, toml::Value::Array(entries)) => tables.extend(
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| (entry_label(group, index, entries.len()), entry)),
+            ),
+            (group, toml::Value::Table(table)) => {
+                if let Some(defaults) = table.get("defaults") {
+                    tables.push((format!("{}.defaults", group), defaults));
+                }
+                let entries = table.get("folders").and_then(toml::Value::as_array);
+                tables.extend(entries.into_iter().flat_map(|entries| {
+                    entries
+                        .iter()
+                        .enumerate()
+                        .map(|(index, entry)| (entry_label(group, index, entries.len()), entry))
+                }));
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+// Extensions are read with their leading dots removed, which quietly changes
+// values like `..rs` into something the user may not have meant.
+fn lint_extension_values(raw: &toml::Value) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (location, table) in settings_tables(raw) {
+        for key in EXTENSION_KEYS {
+            let values = table.get(key).and_then(toml::Value::as_array);
+            let written = values.into_iter().flatten().filter_map(toml::Value::as_str);
+            for extension in written {
+                let read_as = extension.trim_start_matches('.');
+                let message = match read_as {
+                    "" => format!("'{}' in {} doesn't name an extension", extension, key),
+                    _ if extension.starts_with("..") => {
+                        format!("'{}' in {} is read as '{}'", extension, key, read_as)
+                    }
+                    _ => continue,
+                };
+                diagnostics.push(
+                    Diagnostic::new(Severity::Warning, Some(location.clone()), message)
+                        .with_hint("write extensions without the leading dot, e.g. \"rs\""),
+                );
+            }
+        }
+    }
+    diagnostics
+}
+
+fn resolve_directory(directory: &Path, base_dir: Option<&Path>) -> PathBuf {
+    match base_dir {
+        Some(base_dir) if directory.is_relative() && !is_anchored_elsewhere(directory) => {
+            base_dir.join(directory)
+        }
+        _ => directory.to_path_buf(),
+    }
+}
+
+fn is_dangerous_directory(directory: &Path, home_dir: Option<&Path>) -> bool {
+    directory.parent().is_none()
+        || Some(directory) == home_dir
+        || DANGEROUS_DIRECTORIES
+            .iter()
+            .any(|dangerous| directory == Path::new(dangerous))
+}
+
+fn lint_directory(directory: &Path, location: &str, home_dir: Option<&Path>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let location = Some(location.to_string());
+
+    if !directory.is_dir() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Warning,
+            location.clone(),
+            format!("the directory {:?} doesn't exist", directory),
+        ));
+    }
+    if is_dangerous_directory(directory, home_dir) {
+        diagnostics.push(
+            Diagnostic::new(
+                Severity::Error,
+                location,
+                format!("the directory {:?} is a system or home folder", directory),
+            )
+            .with_hint("point the entry at the folder inside it that should be cleaned"),
+        );
+    }
+    diagnostics
+}
+
+fn lint_extensions(entry: &PathConfig, location: &str) -> Vec<Diagnostic> {
+    let to_keep = entry.extensions_to_keep.as_deref().unwrap_or_default();
+    let to_delete = entry.extensions_to_delete.as_deref().unwrap_or_default();
+    to_delete
+        .iter()
+        .filter(|extension| {
+            to_keep
+                .iter()
+                .any(|kept| kept.eq_ignore_ascii_case(extension))
+        })
+        .map(|extension| {
+            Diagnostic::new(
+                Severity::Error,
+                Some(location.to_string()),
+                format!(
+                    "'{}' is listed in both extensions_to_delete and extensions_to_keep",
+                    extension
+                ),
+            )
+            .with_hint("files are kept when both lists match, so remove it from one of them")
+        })
+        .collect()
+}
+
+// Names are matched one file or folder at a time, so a pattern can't span folders.
+fn lint_names(entry: &PathConfig, location: &str) -> Vec<Diagnostic> {
+    let names = entry.names_to_delete.as_deref().unwrap_or_default();
+    names
+        .iter()
+        .filter_map(|name| {
+            let diagnostic = match Pattern::new(name) {
+                Err(error) => Diagnostic::new(
+                    Severity::Error,
+                    Some(location.to_string()),
+                    format!(
+                        "'{}' in names_to_delete isn't a valid pattern: {}",
+                        name, error.msg
+                    ),
+                ),
+                Ok(_) if name.contains('/') => Diagnostic::new(
+                    Severity::Warning,
+                    Some(location.to_string()),
+                    format!("'{}' in names_to_delete will never match", name),
+                )
+                .with_hint(
+                    "names are matched against a single file or folder name, without any '/'",
+                ),
+                Ok(_) if name.starts_with('.') && !entry.delete_hidden => Diagnostic::new(
+                    Severity::Warning,
+                    Some(location.to_string()),
+                    format!(
+                        "'{}' in names_to_delete only matches hidden files, which are skipped",
+                        name
+                    ),
+                )
+                .with_hint("set `delete_hidden = true` to clean them"),
+                Ok(_) => return None,
+            };
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+// The folders an entry's directory expands to, reporting why if it can't be expanded.
+// A glob that matches nothing may just be waiting for its folders to be made, and
+// the entry is skipped until then, so it's only a warning.
+fn lint_expansion(
+    directory: &Path,
+    location: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<PathBuf> {
+    let location = Some(location.to_string());
+    match expand_directory(directory) {
+        Ok(directories) => directories,
+        Err(error @ ConfigError::UnmatchedDirectoryPattern(_)) => {
+            diagnostics.push(
+                Diagnostic::new(Severity::Warning, location, error.to_string())
+                    .with_hint("the entry is skipped until a folder matches it"),
+            );
+            Vec::new()
+        }
+        Err(error) => {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                location,
+                error.to_string(),
+            ));
+            Vec::new()
+        }
+    }
+}
+
+fn lint_config(
+    config: &Config,
+    base_dir: Option<&Path>,
+    home_dir: Option<&Path>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // Where each directory was first seen, to spot the same folder listed twice.
+    let mut seen: HashMap<PathBuf, String> = HashMap::new();
+
+    let groups = match config.resolve_groups() {
+        Ok(groups) => groups.unwrap_or_default(),
+        Err(error) => {
+            diagnostics.push(Diagnostic::new(Severity::Error, None, error.to_string()));
+            return diagnostics;
+        }
+    };
+
+    for (group, entries) in &groups {
+        for (index, entry) in entries.iter().enumerate() {
+            let location = entry_label(group, index, entries.len());
+            diagnostics.extend(lint_extensions(entry, &location));
+            diagnostics.extend(lint_names(entry, &location));
+
+            let directory = resolve_directory(&entry.directory, base_dir);
+            for directory in lint_expansion(&directory, &location, &mut diagnostics) {
+                diagnostics.extend(lint_directory(&directory, &location, home_dir));
+
+                let key = canonicalize(&directory).unwrap_or(directory);
+                match seen.get(&key) {
+                    Some(first) => diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Warning,
+                            Some(location.clone()),
+                            format!("the directory {:?} is also listed in {}", key, first),
+                        )
+                        .with_hint(
+                            "tags can select a folder from several places without repeating it",
+                        ),
+                    ),
+                    None => {
+                        seen.insert(key, location.clone());
+                    }
+                }
+            }
+        }
+    }
+    diagnostics
+}
+
+fn validate_source(
+    content: &str,
+    format: ConfigFormat,
+    base_dir: Option<&Path>,
+    home_dir: Option<&Path>,
+) -> Vec<Diagnostic> {
+    let config = match parse_config_from_str(content, format) {
+        Ok(config) => config,
+        Err(error) => return vec![parse_diagnostic(content, &error)],
+    };
+
+    let mut diagnostics = lint_config(&config, base_dir, home_dir);
+    if let Ok(raw) = parse_raw_config_from_str(content, format) {
+        diagnostics.extend(lint_extension_values(&raw));
+    }
+    diagnostics
+}
+
+/// The problems in the edited TOML config that weren't already in the original, so an
+/// edit isn't blamed for what was wrong before it.
+pub fn lint_edit(original: &str, edited: &str, home_dir: Option<&Path>) -> Vec<Diagnostic> {
+    let mut existing = validate_source(original, ConfigFormat::Toml, None, home_dir);
+    // Entries are labelled by their position, so problems are matched by message alone.
+    validate_source(edited, ConfigFormat::Toml, None, home_dir)
+        .into_iter()
+        .filter(|diagnostic| {
+            let index = existing.iter().position(|old| {
+                old.severity == diagnostic.severity && old.message == diagnostic.message
+            });
+            index.map(|index| existing.remove(index)).is_none()
+        })
+        .collect()
+}
+
+/// Parses a config layer and checks it for mistakes that would stop it from
+/// loading or make it clean something other than what was intended.
+///
+/// # Returns
+///
+/// A report listing every problem found, or an error if the file couldn't be read.
+pub fn validate_config_layer(
+    layer: &ConfigLayer,
+    home_dir: Option<&Path>,
+) -> Result<ValidationReport, ConfigError> {
+    let content =
+        fs::read_to_string(&layer.path).map_err(|_| ConfigError::read_error(&layer.path))?;
+    // Project configs resolve relative directories against their own folder.
+    let base_dir = match layer.kind {
+        LayerKind::Project => layer.path.parent(),
+        _ => None,
+    };
+    Ok(ValidationReport {
+        path: layer.path.clone(),
+        diagnostics: validate_source(
+            &content,
+            ConfigFormat::from_path(&layer.path),
+            base_dir,
+            home_dir,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_errors_point_at_the_line() {
+        let content = "[[downloads]]\ndirectory = \"/tmp\"\nextension_to_delete = [\"log\"]\n";
+        let diagnostics = validate_source(content, ConfigFormat::Toml, None, None);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].message.contains("unknown field"));
+        assert!(diagnostics[0].hint.is_some());
+        // Unknown keys are reported at the table holding them
+        assert!(diagnostics[0]
+            .snippet
+            .as_ref()
+            .unwrap()
+            .contains("1 | [[downloads]]"));
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_the_key_they_name() {
+        let content = "[[build]]\ndirectory = \"/tmp\"\nrecursive = true\n\n\
+                       [[logs]]\ndirectory = \"/tmp\"\nrecursive = 5\n\n\
+                       [docs]\nfolders = [{ directory = \"/tmp\", older_than_days = -1 }]\n";
+        let snippet = |content: &str| {
+            let diagnostics = validate_source(content, ConfigFormat::Toml, None, None);
+            diagnostics[0].snippet.clone().unwrap()
+        };
+
+        assert!(snippet(content).contains("7 | recursive = 5"));
+        let inline = content.replace("recursive = 5", "recursive = false");
+        assert!(snippet(&inline).contains("10 | folders = [{"));
+    }
+
+    #[test]
+    fn test_repeated_keys_point_at_the_group_that_failed() {
+        let content = "[[build]]\ndirectory = \"/tmp\"\nrecursive = 5\n\n\
+                       [[logs]]\ndirectory = \"/tmp\"\nrecursive = 5\n";
+        let diagnostics = validate_source(content, ConfigFormat::Toml, None, None);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0]
+            .snippet
+            .as_ref()
+            .unwrap()
+            .contains("3 | recursive = 5"));
+    }
+
+    #[test]
+    fn test_lints_entries() {
+        let dir = tempdir().unwrap();
+        let folder = dir.path().to_string_lossy();
+        let content = format!(
+            "[[logs]]\ndirectory = \"{folder}\"\n\
+             extensions_to_delete = [\"log\", \"..rs\"]\nextensions_to_keep = [\"LOG\"]\n\n\
+             [[work]]\ndirectory = \"{folder}\"\n\n\
+             [[work]]\ndirectory = \"missing\"\n\n\
+             [[system]]\ndirectory = \"/usr\"\n\n\
+             [[stale]]\ndirectory = \"{folder}/*/missing\"\n\n\
+             [[editor]]\ndirectory = \"{folder}/editor\"\nnames_to_delete = [\"[*~\", \"src/*~\", \".DS_Store\"]\n"
+        );
+
+        let diagnostics = validate_source(&content, ConfigFormat::Toml, Some(dir.path()), None);
+        let messages: Vec<(Severity, Option<&str>, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.location.as_deref(), d.message.as_str()))
+            .collect();
+
+        assert!(messages.contains(&(
+            Severity::Error,
+            Some("logs"),
+            "'log' is listed in both extensions_to_delete and extensions_to_keep"
+        )));
+        assert!(messages.contains(&(
+            Severity::Warning,
+            Some("logs"),
+            "'..rs' in extensions_to_delete is read as 'rs'"
+        )));
+        assert!(messages
+            .iter()
+            .any(|(_, location, message)| *location == Some("work #1")
+                && message.contains("is also listed in logs")));
+        assert!(messages
+            .iter()
+            .any(|(_, location, message)| *location == Some("work #2")
+                && message.contains("doesn't exist")));
+        assert!(messages
+            .iter()
+            .any(|(severity, location, _)| *severity == Severity::Error
+                && *location == Some("system")));
+        assert!(messages
+            .iter()
+            .any(
+                |(severity, location, message)| *severity == Severity::Warning
+                    && *location == Some("stale")
+                    && message.contains("doesn't match any folders")
+            ));
+        assert!(messages.iter().any(|(severity, location, message)| {
+            *severity == Severity::Error
+                && *location == Some("editor")
+                && message.starts_with("'[*~' in names_to_delete isn't a valid pattern")
+        }));
+        assert!(messages.contains(&(
+            Severity::Warning,
+            Some("editor"),
+            "'src/*~' in names_to_delete will never match"
+        )));
+        assert!(messages.contains(&(
+            Severity::Warning,
+            Some("editor"),
+            "'.DS_Store' in names_to_delete only matches hidden files, which are skipped"
+        )));
+    }
+
+    #[test]
+    fn test_home_and_root_are_dangerous() {
+        let home = Path::new("/home/user");
+        assert!(is_dangerous_directory(Path::new("/"), Some(home)));
+        assert!(is_dangerous_directory(home, Some(home)));
+        assert!(!is_dangerous_directory(&home.join("Downloads"), Some(home)));
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+
+use serde_derive::Serialize;
+
+use super::machine_output::SCHEMA_VERSION;
+use super::style::current_style;
+use super::DASHED_LINE;
+use crate::configs::config::PathConfig;
+use crate::utils::escape_path;
+
+// Longer extension lists are cut short in the group list, to keep each row on one line.
+const SUMMARY_EXTENSION_LIMIT: usize = 4;
+
+/// A folder from the config with every setting resolved, along with the command line
+/// flags that changed it.
+pub struct ResolvedFolder {
+    pub config: PathConfig,
+    pub overrides: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct GroupRecord<'a> {
+    name: &'a str,
+    directories: Vec<String>,
+    rules: String,
+}
+
+#[derive(Serialize)]
+struct FolderConfigRecord<'a> {
+    group: &'a Option<String>,
+    directory: String,
+    extensions_to_delete: &'a Option<Vec<String>>,
+    extensions_to_keep: &'a Option<Vec<String>>,
+    names_to_delete: &'a Option<Vec<String>>,
+    recursive: bool,
+    delete_hidden: bool,
+    older_than_days: Option<u64>,
+    tags: &'a [String],
+    overrides: &'a [String],
+}
+
+#[derive(Serialize)]
+struct GroupListReport<'a> {
+    schema_version: u32,
+    groups: Vec<GroupRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct FolderConfigReport<'a> {
+    schema_version: u32,
+    folders: Vec<FolderConfigRecord<'a>>,
+}
+
+fn format_limited_list(items: &[String], limit: usize) -> String {
+    let mut shown: Vec<String> = items.iter().take(limit).cloned().collect();
+    if items.len() > limit {
+        shown.push(format!("+{} more", items.len() - limit));
+    }
+    shown.join(", ")
+}
+
+fn format_extension_list(extensions: &[String], limit: usize) -> String {
+    let dotted: Vec<String> = extensions.iter().map(|ext| format!(".{}", ext)).collect();
+    format_limited_list(&dotted, limit)
+}
+
+/// A one line description of what a folder's filters delete, e.g.
+/// "deletes .log, .tmp; older than 14 days".
+pub fn summarise_rules(config: &PathConfig) -> String {
+    let matched: Vec<String> = (config.extensions_to_delete.iter().flatten())
+        .map(|ext| format!(".{}", ext))
+        .chain(config.names_to_delete.iter().flatten().cloned())
+        .collect();
+    let mut parts = vec![match matched.is_empty() {
+        true => "deletes any extension".to_string(),
+        false => format!(
+            "deletes {}",
+            format_limited_list(&matched, SUMMARY_EXTENSION_LIMIT)
+        ),
+    }];
+    if let Some(exts) = &config.extensions_to_keep {
+        parts.push(format!(
+            "keeps {}",
+            format_extension_list(exts, SUMMARY_EXTENSION_LIMIT)
+        ));
+    }
+    if let Some(days) = config.older_than_days {
+        parts.push(format!("older than {} days", days));
+    }
+    if config.recursive {
+        parts.push("including subfolders".to_string());
+    }
+    if config.delete_hidden {
+        parts.push("including hidden files".to_string());
+    }
+    parts.join("; ")
+}
+
+// Folders in a group usually share their rules, so each distinct summary is only listed once.
+fn summarise_group_rules(configs: &[PathConfig]) -> String {
+    let mut summaries: Vec<String> = Vec::new();
+    for summary in configs.iter().map(summarise_rules) {
+        if !summaries.contains(&summary) {
+            summaries.push(summary);
+        }
+    }
+    summaries.join(" | ")
+}
+
+fn group_records(groups: &BTreeMap<String, Vec<PathConfig>>) -> Vec<GroupRecord<'_>> {
+    groups
+        .iter()
+        .map(|(name, configs)| GroupRecord {
+            name,
+            directories: configs
+                .iter()
+                .map(|config| escape_path(&config.directory))
+                .collect(),
+            rules: summarise_group_rules(configs),
+        })
+        .collect()
+}
+
+fn folder_records(folders: &[ResolvedFolder]) -> Vec<FolderConfigRecord<'_>> {
+    folders
+        .iter()
+        .map(|folder| FolderConfigRecord {
+            group: &folder.config.group,
+            directory: escape_path(&folder.config.directory),
+            extensions_to_delete: &folder.config.extensions_to_delete,
+            extensions_to_keep: &folder.config.extensions_to_keep,
+            names_to_delete: &folder.config.names_to_delete,
+            recursive: folder.config.recursive,
+            delete_hidden: folder.config.delete_hidden,
+            older_than_days: folder.config.older_than_days,
+            tags: &folder.config.tags,
+            overrides: &folder.overrides,
+        })
+        .collect()
+}
+
+/// Lists each group with its number of directories and a summary of its rules.
+pub fn generate_group_list_text(groups: &BTreeMap<String, Vec<PathConfig>>) -> String {
+    let style = current_style();
+    let mut lines = vec![
+        style.bold(&format!(
+            "  {:<16} {:>11}  {}",
+            "Group", "Directories", "Rules"
+        )),
+        DASHED_LINE.to_string(),
+    ];
+    lines.extend(group_records(groups).iter().map(|group| {
+        format!(
+            "  {:<16} {:>11}  {}",
+            group.name,
+            group.directories.len(),
+            group.rules
+        )
+    }));
+    lines.join("\n")
+}
+
+pub fn generate_group_list_json(groups: &BTreeMap<String, Vec<PathConfig>>) -> String {
+    let report = GroupListReport {
+        schema_version: SCHEMA_VERSION,
+        groups: group_records(groups),
+    };
+    serde_json::to_string_pretty(&report).expect("Group records are always serialisable")
+}
+
+/// Renders one compact JSON record per group.
+pub fn generate_group_list_ndjson(groups: &BTreeMap<String, Vec<PathConfig>>) -> String {
+    group_records(groups)
+        .iter()
+        .map(|record| serde_json::to_string(record).expect("Group records are always serialisable"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn format_setting(label: &str, value: String) -> String {
+    format!("  {:<24} {}", format!("{}:", label), value)
+}
+
+fn format_optional_extensions(extensions: &Option<Vec<String>>, missing: &str) -> String {
+    match extensions {
+        Some(exts) => format_extension_list(exts, exts.len()),
+        None => missing.to_string(),
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    match value {
+        true => "yes".to_string(),
+        false => "no".to_string(),
+    }
+}
+
+fn format_resolved_folder(folder: &ResolvedFolder) -> String {
+    let style = current_style();
+    let config = &folder.config;
+    let title = config.group.as_deref().unwrap_or("(no group)");
+
+    let mut lines = vec![
+        style.bold(title),
+        format_setting("Directory", escape_path(&config.directory)),
+        format_setting(
+            "Extensions to delete",
+            format_optional_extensions(&config.extensions_to_delete, "any"),
+        ),
+        format_setting(
+            "Extensions to keep",
+            format_optional_extensions(&config.extensions_to_keep, "none"),
+        ),
+        format_setting(
+            "Names to delete",
+            config
+                .names_to_delete
+                .as_ref()
+                .map(|names| names.join(", "))
+                .unwrap_or_else(|| "none".to_string()),
+        ),
+        format_setting(
+            "Only files older than",
+            config
+                .older_than_days
+                .map(|days| format!("{} days", days))
+                .unwrap_or_else(|| "any age".to_string()),
+        ),
+        format_setting("Delete hidden files", yes_no(config.delete_hidden)),
+        format_setting("Recursive", yes_no(config.recursive)),
+        format_setting(
+            "Tags",
+            match config.tags.is_empty() {
+                true => "none".to_string(),
+                false => config.tags.join(", "),
+            },
+        ),
+    ];
+    if !folder.overrides.is_empty() {
+        lines.push(format_setting(
+            "Set on the command line",
+            folder.overrides.join(", "),
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Prints every setting of each folder, including the ones left at their defaults.
+pub fn generate_folder_config_text(folders: &[ResolvedFolder]) -> String {
+    folders
+        .iter()
+        .map(format_resolved_folder)
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+pub fn generate_folder_config_json(folders: &[ResolvedFolder]) -> String {
+    let report = FolderConfigReport {
+        schema_version: SCHEMA_VERSION,
+        folders: folder_records(folders),
+    };
+    serde_json::to_string_pretty(&report).expect("Folder configs are always serialisable")
+}
+
+/// Renders one compact JSON record per folder.
+pub fn generate_folder_config_ndjson(folders: &[ResolvedFolder]) -> String {
+    folder_records(folders)
+        .iter()
+        .map(|record| {
+            serde_json::to_string(record).expect("Folder configs are always serialisable")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn folder(directory: &str, extensions: &[&str], older_than_days: Option<u64>) -> PathConfig {
+        let mut config = PathConfig::new(PathBuf::from(directory), true);
+        config.group = Some("logs".to_string());
+        config.extensions_to_delete = Some(extensions.iter().map(|ext| ext.to_string()).collect());
+        config.older_than_days = older_than_days;
+        config
+    }
+
+    #[test]
+    fn test_rule_summary_shortens_long_lists() {
+        let config = folder("/var/app", &["a", "b", "c", "d", "e", "f"], Some(14));
+        assert_eq!(
+            summarise_rules(&config),
+            "deletes .a, .b, .c, .d, +2 more; older than 14 days"
+        );
+        assert_eq!(
+            summarise_rules(&PathConfig::new(PathBuf::from("/tmp"), true)),
+            "deletes any extension"
+        );
+    }
+
+    #[test]
+    fn test_rule_summary_mentions_subfolders_and_hidden_files() {
+        let mut config = folder("/var/app", &["log"], None);
+        config.recursive = true;
+        config.delete_hidden = true;
+        assert_eq!(
+            summarise_rules(&config),
+            "deletes .log; including subfolders; including hidden files"
+        );
+    }
+
+    #[test]
+    fn test_group_list_json_merges_shared_rules() {
+        let mut groups = BTreeMap::new();
+        groups.insert(
+            "logs".to_string(),
+            vec![
+                folder("/var/app", &["log"], None),
+                folder("/var/web", &["log"], None),
+                folder("/var/db", &["log"], Some(7)),
+            ],
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&generate_group_list_json(&groups)).unwrap();
+        let group = &json["groups"][0];
+        assert_eq!(group["name"], "logs");
+        assert_eq!(group["directories"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            group["rules"],
+            "deletes .log | deletes .log; older than 7 days"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use csv::{Writer, WriterBuilder};
+
+use super::folder_tree_helpers::DirTreeLeaf;
+use super::process_directory_tree::FileSystemStack;
+use crate::configs::config::PathConfig;
+use crate::utils::escape_path;
+
+const HEADERS: [&str; 9] = [
+    "entry_type",
+    "path",
+    "relative_path",
+    "depth",
+    "size_bytes",
+    "modified",
+    "extension",
+    "group",
+    "rule",
+];
+
+/// Writes the deletion candidates of one or more folders to a CSV or TSV file.
+///
+/// The delimiter is picked from the file extension: `.tsv` files are tab separated,
+/// everything else is comma separated. Fields containing delimiters, quotes or
+/// newlines are quoted. Paths that aren't valid UTF-8 have their invalid bytes
+/// written as `\xNN`.
+pub struct CandidateExporter {
+    writer: Writer<File>,
+    include_directories: bool,
+}
+
+impl CandidateExporter {
+    pub fn create(export_path: &Path, include_directories: bool) -> io::Result<Self> {
+        let delimiter = match export_path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("tsv") => b'\t',
+            _ => b',',
+        };
+
+        let mut writer = WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_path(export_path)?;
+        writer.write_record(HEADERS)?;
+
+        Ok(CandidateExporter {
+            writer,
+            include_directories,
+        })
+    }
+
+    /// Appends a row for every candidate file in `stack`, plus a summary row for
+    /// each directory if directory rows were requested. Rows are flushed to disk
+    /// before returning, as the deletion prompt may exit the program.
+    pub fn write_folder(&mut self, config: &PathConfig, stack: &FileSystemStack) -> io::Result<()> {
+        let directory_totals = match self.include_directories {
+            true => summarise_directories(&config.directory, stack),
+            false => HashMap::new(),
+        };
+
+        for leaf in stack {
+            if leaf.is_scheduled_file() {
+                self.writer.write_record(file_row(config, leaf))?;
+            } else if leaf.is_dir && self.include_directories {
+                let total_size = directory_totals.get(&leaf.key).copied().unwrap_or(0);
+                self.writer
+                    .write_record(directory_row(config, leaf, total_size))?;
+            }
+        }
+
+        self.writer.flush()
+    }
+}
+
+fn relative_path(config: &PathConfig, path: &Path) -> String {
+    match path.strip_prefix(&config.directory) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => escape_path(relative),
+        Err(_) => escape_path(path),
+    }
+}
+
+fn file_row(config: &PathConfig, leaf: &DirTreeLeaf) -> Vec<String> {
+    let modified = leaf
+        .modified
+        .map(|time| DateTime::<Local>::from(time).to_rfc3339())
+        .unwrap_or_default();
+    let extension = leaf
+        .key
+        .extension()
+        .map(|ext| escape_path(Path::new(ext)))
+        .unwrap_or_default();
+
+    vec![
+        "file".to_string(),
+        escape_path(&leaf.key),
+        relative_path(config, &leaf.key),
+        leaf.depth.to_string(),
+        leaf.size.to_string(),
+        modified,
+        extension,
+        config.group.clone().unwrap_or_default(),
+        leaf.rule
+            .as_ref()
+            .map(|r| r.to_string())
+            .unwrap_or_default(),
+    ]
+}
+
+fn directory_row(config: &PathConfig, leaf: &DirTreeLeaf, total_size: u64) -> Vec<String> {
+    vec![
+        "directory".to_string(),
+        escape_path(&leaf.key),
+        relative_path(config, &leaf.key),
+        leaf.depth.to_string(),
+        total_size.to_string(),
+        String::new(),
+        String::new(),
+        config.group.clone().unwrap_or_default(),
+        String::new(),
+    ]
+}
+
+// Totals the size of the candidate files beneath each directory in the stack.
+fn summarise_directories(root: &Path, stack: &FileSystemStack) -> HashMap<PathBuf, u64> {
+    let mut totals: HashMap<PathBuf, u64> = HashMap::new();
+
+    for leaf in stack.iter().filter(|leaf| leaf.is_scheduled_file()) {
+        for ancestor in leaf.key.ancestors().skip(1) {
+            *totals.entry(ancestor.to_path_buf()).or_default() += leaf.size;
+            if ancestor == root {
+                break;
+            }
+        }
+    }
+
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::mark_for_deletion::DeletionRule;
+    use std::collections::VecDeque;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn build_stack(root: &Path) -> FileSystemStack {
+        let awkward_file = root.join("report, final\nv2.txt");
+        fs::write(&awkward_file, "hello").unwrap();
+
+        let mut stack: FileSystemStack = VecDeque::new();
+        stack.push_back(DirTreeLeaf::new_root(root.to_path_buf()));
+        stack.push_back(DirTreeLeaf {
+            key: awkward_file,
+            depth: 1,
+            size: 5,
+            rule: Some(DeletionRule::MatchedExtension("txt".to_string())),
+            ..Default::default()
+        });
+        stack
+    }
+
+    fn export_and_read(file_name: &str, include_directories: bool) -> Vec<csv::StringRecord> {
+        let dir = tempdir().unwrap();
+        let scanned = dir.path().join("scanned");
+        fs::create_dir(&scanned).unwrap();
+
+        let mut config = PathConfig::new(scanned.clone(), true);
+        config.group = Some("docs".to_string());
+
+        let export_path = dir.path().join(file_name);
+        let mut exporter = CandidateExporter::create(&export_path, include_directories).unwrap();
+        exporter
+            .write_folder(&config, &build_stack(&scanned))
+            .unwrap();
+
+        let delimiter = if file_name.ends_with(".tsv") {
+            b'\t'
+        } else {
+            b','
+        };
+        csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .from_path(&export_path)
+            .unwrap()
+            .records()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_csv_export_escapes_commas_and_newlines() {
+        let rows = export_and_read("candidates.csv", false);
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][0], "file");
+        assert_eq!(&rows[0][2], "report, final\nv2.txt");
+        assert_eq!(&rows[0][3], "1");
+        assert_eq!(&rows[0][4], "5");
+        assert_eq!(&rows[0][6], "txt");
+        assert_eq!(&rows[0][7], "docs");
+        assert_eq!(&rows[0][8], "extension .txt");
+    }
+
+    #[test]
+    fn test_tsv_export_with_directory_rows() {
+        let rows = export_and_read("candidates.tsv", true);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][0], "directory");
+        assert_eq!(&rows[0][2], ".");
+        assert_eq!(&rows[0][4], "5");
+        assert_eq!(&rows[1][0], "file");
+    }
+}
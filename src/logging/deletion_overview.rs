@@ -1,36 +1,47 @@
+use std::collections::BTreeMap;
 use std::time::SystemTime;
 
+use super::style::current_style;
+use super::{DASHED_LINE, LINE};
+use crate::cleaning::track_files_for_deletion::{DeletionMetaData, ExtensionStats};
+use crate::configs::categories::CategoryMap;
+use crate::configs::config::PathConfig;
 use crate::utils::format_size;
-use crate::{cleaning::track_files_for_deletion::DeletionMetaData, configs::config::PathConfig};
 use chrono::{DateTime, Local};
 
-const DASHED_LINE: &str = "---------------------------------------------------------";
-const LINE: &str = "=========================================================";
+// Breakdown tables only show the largest rows, to keep the overview readable.
+const BREAKDOWN_ROW_LIMIT: usize = 10;
 
 fn bold(text: &str) -> String {
-    format!("\x1b[1m{}\x1b[0m", text)
+    current_style().bold(text)
 }
 
 fn deletion_overview_text() -> Vec<String> {
-    [LINE, "📁 Cleaning Overview 📁", DASHED_LINE]
+    let heading = current_style().heading("📁", "Cleaning Overview");
+    [LINE, &heading, DASHED_LINE]
         .iter()
         .map(|s| s.to_string())
         .collect()
 }
 
 fn folder_size_overview_text() -> Vec<String> {
-    [LINE, "📁 Folder Size Overview 📁", DASHED_LINE]
+    let heading = current_style().heading("📁", "Folder Size Overview");
+    [LINE, &heading, DASHED_LINE]
         .iter()
         .map(|s| s.to_string())
         .collect()
 }
 
 fn deletion_warning() -> Vec<String> {
+    let style = current_style();
     [
         DASHED_LINE,
-        &bold("🚨 WARNING: This action is irreversible 🚨"),
-        "🛑 Ensure you've backed up any important data before proceeding.",
-        "🔍 Review the information carefully before proceeding.",
+        &bold(&style.heading("🚨", "WARNING: This action is irreversible")),
+        &style.prefixed(
+            "🛑",
+            "Ensure you've backed up any important data before proceeding.",
+        ),
+        &style.prefixed("🔍", "Review the information carefully before proceeding."),
         LINE,
     ]
     .iter()
@@ -78,11 +89,11 @@ fn format_last_modified(last_modified: SystemTime) -> String {
     format!(
         "{}: {}",
         bold("Last modified date"),
-        last_modified.format("%Y-%m-%d %H:%M:%S %Z").to_string()
+        last_modified.format("%Y-%m-%d %H:%M:%S %Z")
     )
 }
 
-fn create_extensions_string(extensions: &Vec<String>) -> String {
+fn create_extensions_string(extensions: &[String]) -> String {
     let formatted: Vec<String> = extensions.iter().map(|ext| format!(".{}", ext)).collect();
     format!("({})", formatted.join(", "))
 }
@@ -101,12 +112,109 @@ fn format_extensions(config: &PathConfig) -> Vec<String> {
         let extensions = create_extensions_string(exts);
         extension_texts.push(format!("{}: {:?}", bold("Extensions to keep"), extensions));
     }
+    if let Some(names) = &config.names_to_delete {
+        let names = format!("({})", names.join(", "));
+        extension_texts.push(format!(
+            "{}: {:?}",
+            bold("Names marked for deletion"),
+            names
+        ));
+    }
+    if config.delete_hidden {
+        extension_texts.push(format!("{}: yes", bold("Delete hidden files")));
+    }
+    if let Some(days) = config.older_than_days {
+        extension_texts.push(format!("{}: {} days", bold("Only files older than"), days));
+    }
     extension_texts
 }
 
+fn format_breakdown_row(label: &str, stats: &ExtensionStats, folder_size: u64) -> String {
+    let share = match folder_size {
+        0 => 0.0,
+        _ => stats.size as f64 * 100.0 / folder_size as f64,
+    };
+    format!(
+        "  {:<16} {:>7} files {:>12} {:>6.1}%",
+        label,
+        stats.file_count,
+        format_size(stats.size),
+        share
+    )
+}
+
+fn format_breakdown_table(
+    title: &str,
+    rows: Vec<(String, ExtensionStats)>,
+    folder_size: u64,
+    noun: &str,
+) -> Vec<String> {
+    if rows.is_empty() {
+        return vec![];
+    }
+
+    let mut rows = rows;
+    rows.sort_by(|(a_label, a), (b_label, b)| b.size.cmp(&a.size).then(a_label.cmp(b_label)));
+
+    let mut table = vec![DASHED_LINE.to_string(), bold(title)];
+    for (label, stats) in rows.iter().take(BREAKDOWN_ROW_LIMIT) {
+        table.push(format_breakdown_row(label, stats, folder_size));
+    }
+    if rows.len() > BREAKDOWN_ROW_LIMIT {
+        table.push(format!(
+            "  {} and {} more {}",
+            current_style().symbol("…", "..."),
+            rows.len() - BREAKDOWN_ROW_LIMIT,
+            noun
+        ));
+    }
+    table
+}
+
+fn format_extension_breakdown(metadata: &DeletionMetaData) -> Vec<String> {
+    let rows = metadata
+        .extension_stats
+        .iter()
+        .map(|(ext, stats)| {
+            let label = match ext.is_empty() {
+                true => "(no extension)".to_string(),
+                false => format!(".{}", ext),
+            };
+            (label, stats.clone())
+        })
+        .collect();
+
+    format_breakdown_table(
+        "Breakdown by extension",
+        rows,
+        metadata.folder_size,
+        "extensions",
+    )
+}
+
+fn format_category_breakdown(config: &PathConfig, metadata: &DeletionMetaData) -> Vec<String> {
+    let category_map = CategoryMap::with_overrides(config.categories.as_ref());
+    let mut categories: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+
+    for (ext, stats) in metadata.extension_stats.iter() {
+        let category_stats = categories
+            .entry(category_map.category_for(ext).to_string())
+            .or_default();
+        category_stats.file_count += stats.file_count;
+        category_stats.size += stats.size;
+    }
+
+    format_breakdown_table(
+        "Breakdown by category",
+        categories.into_iter().collect(),
+        metadata.folder_size,
+        "categories",
+    )
+}
+
 pub fn generate_deletion_overview_text(
     config: &PathConfig, // Assume this is the correct reference to PathConfig
-    deletion_metadata: DeletionMetaData,
+    deletion_metadata: &DeletionMetaData,
 ) -> String {
     let mut deletion_overview: Vec<String> = vec![];
     deletion_overview.extend(deletion_overview_text());
@@ -114,9 +222,11 @@ pub fn generate_deletion_overview_text(
     // Log folder metadata
     deletion_overview.push(format_folder_path(config));
     deletion_overview.push(format_total_size(deletion_metadata.folder_size));
-    deletion_overview.push(format_deletion_size(&deletion_metadata));
+    deletion_overview.push(format_deletion_size(deletion_metadata));
     deletion_overview.push(format_last_modified(deletion_metadata.last_modified_time));
     deletion_overview.extend(format_extensions(config));
+    deletion_overview.extend(format_extension_breakdown(deletion_metadata));
+    deletion_overview.extend(format_category_breakdown(config, deletion_metadata));
     // Generate warning before asking for deletion confirmation
     deletion_overview.extend(deletion_warning());
 
@@ -126,17 +236,78 @@ pub fn generate_deletion_overview_text(
 
 pub fn generate_size_overview_text(
     config: &PathConfig, // Assume this is the correct reference to PathConfig
-    metadata: DeletionMetaData,
+    metadata: &DeletionMetaData,
 ) -> String {
     let mut size_overview: Vec<String> = vec![];
     size_overview.extend(folder_size_overview_text());
 
     // Log folder metadata
     size_overview.push(format_folder_path(config));
-    size_overview.push(format_file_folder_counts(&metadata));
+    size_overview.push(format_file_folder_counts(metadata));
     size_overview.push(format_last_modified(metadata.last_modified_time));
     size_overview.extend(format_extensions(config));
+    size_overview.extend(format_extension_breakdown(metadata));
+    size_overview.extend(format_category_breakdown(config, metadata));
 
     // Return w/ newline separated strings
     size_overview.join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn metadata_with_extensions(extensions: &[(&str, usize, u64)]) -> DeletionMetaData {
+        let mut metadata = DeletionMetaData::from_root_folder(&PathBuf::from("missing"));
+        for (ext, file_count, size) in extensions {
+            metadata.folder_size += size;
+            metadata.extension_stats.insert(
+                ext.to_string(),
+                ExtensionStats {
+                    file_count: *file_count,
+                    size: *size,
+                },
+            );
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_extension_breakdown_sorted_by_size() {
+        let metadata = metadata_with_extensions(&[("log", 2, 256), ("mp4", 1, 768), ("", 1, 0)]);
+        let table = format_extension_breakdown(&metadata);
+
+        assert_eq!(table.len(), 5);
+        assert!(table[2].starts_with("  .mp4"));
+        assert!(table[2].ends_with("75.0%"));
+        assert!(table[3].starts_with("  .log"));
+        assert!(table[4].starts_with("  (no extension)"));
+    }
+
+    #[test]
+    fn test_extension_breakdown_truncates_long_tables() {
+        let extensions: Vec<String> = (0..15).map(|i| format!("e{}", i)).collect();
+        let rows: Vec<(&str, usize, u64)> = extensions.iter().map(|e| (e.as_str(), 1, 1)).collect();
+        let table = format_extension_breakdown(&metadata_with_extensions(&rows));
+
+        assert_eq!(table.len(), 2 + BREAKDOWN_ROW_LIMIT + 1);
+        assert_eq!(table.last().unwrap(), "  … and 5 more extensions");
+    }
+
+    #[test]
+    fn test_category_breakdown_uses_overrides() {
+        let metadata = metadata_with_extensions(&[("png", 1, 10), ("jpg", 1, 10), ("psd", 1, 30)]);
+        let mut config = PathConfig::new(PathBuf::from("missing"), true);
+        config.categories = Some(
+            [("design".to_string(), vec!["psd".to_string()])]
+                .into_iter()
+                .collect(),
+        );
+
+        let table = format_category_breakdown(&config, &metadata);
+        assert!(table[2].starts_with("  design"));
+        assert!(table[3].starts_with("  images"));
+        assert!(table[3].contains(" 2 files"));
+    }
+}
@@ -1,47 +1,161 @@
-use std::{fmt, path::PathBuf};
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+use std::{fmt, time::SystemTime};
 
-use crate::utils::format_size;
+use super::style::current_style;
+use crate::cleaning::mark_for_deletion::{DeletionRule, KeepReason};
+use crate::utils::{escape_path, format_size};
+
+/// The order in which the entries of each directory are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TreeSortOrder {
+    /// Alphabetically, by name.
+    #[default]
+    Name,
+    /// Largest first.
+    Size,
+    /// Most recently modified first.
+    Mtime,
+}
 
 pub struct DirTreeOptions {
     pub display_files: bool,
-    pub file_size: Option<u64>,
+    // Append the size of each entry to its line
+    pub show_sizes: bool,
+    // Show how much of each directory is scheduled for deletion
+    pub show_scheduled: bool,
+    // Mark each file as deleted or kept, along with the reason
+    pub show_status: bool,
+    // Size trees don't delete anything, so only their kept files are marked
+    pub show_deleted: bool,
+    // Include files that won't be deleted, if the scan tracked them
+    pub show_kept: bool,
+    // Entries deeper than this (relative to the root) aren't printed
+    pub max_depth: Option<usize>,
+    pub sort: TreeSortOrder,
+    // Files beyond this many in a single directory are folded into one line
+    pub collapse_after: Option<usize>,
+    // Label entries with their full path rather than their name
+    pub full_paths: bool,
 }
 
 impl Default for DirTreeOptions {
     fn default() -> Self {
         DirTreeOptions {
             display_files: true,
-            file_size: None,
+            show_sizes: false,
+            show_scheduled: false,
+            show_status: false,
+            show_deleted: true,
+            show_kept: true,
+            max_depth: None,
+            sort: TreeSortOrder::Name,
+            collapse_after: None,
+            full_paths: false,
         }
     }
 }
 
 impl DirTreeOptions {
-    pub fn should_skip_file_leaf(&self, path: &PathBuf) -> bool {
-        // Skip if `display_files` is true and the path is a file
-        match path.is_file() {
-            // If we don't want to display, we should skip files -> invert
-            true => !self.display_files,
-            false => false,
+    pub fn should_skip_leaf(&self, is_dir: bool) -> bool {
+        // Directories are always shown, files only if `display_files` is set
+        !is_dir && !self.display_files
+    }
+
+    pub fn should_skip_kept_file(&self, leaf: &DirTreeLeaf) -> bool {
+        !self.show_kept && leaf.keep_reason.is_some()
+    }
+
+    pub fn is_beyond_max_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max_depth| depth > max_depth)
+    }
+
+    pub fn get_tree_suffix_str(&self, leaf: &DirTreeLeaf) -> String {
+        let mut suffix = self.get_size_suffix_str(leaf);
+        if self.show_status {
+            suffix += &get_status_marker(leaf, self.show_deleted);
         }
+        suffix
     }
 
-    pub fn get_tree_suffix_str(&self) -> String {
-        match self.file_size {
-            Some(size) => {
-                format!(" - \x1b[1m{}\x1b[0m", format_size(size))
-            }
-            // Default to empty string if no suffix is supplied
-            None => String::new(),
+    fn get_size_suffix_str(&self, leaf: &DirTreeLeaf) -> String {
+        // Default to empty string if sizes aren't requested
+        if !self.show_sizes {
+            return String::new();
         }
+
+        let style = current_style();
+        let size = style.bold(&format_size(leaf.size));
+        if !leaf.is_dir {
+            return format!(" - {}", size);
+        }
+
+        let totals = &leaf.totals;
+        match self.show_scheduled {
+            true => format!(
+                " - {} scheduled of {} total ({} of {} files, {} dirs)",
+                style.bold(&format_size(totals.deletion_size)),
+                size,
+                totals.deletion_count,
+                totals.file_count,
+                totals.dir_count
+            ),
+            false => format!(
+                " - {} ({} files, {} dirs)",
+                size, totals.file_count, totals.dir_count
+            ),
+        }
+    }
+}
+
+fn get_status_marker(leaf: &DirTreeLeaf, show_deleted: bool) -> String {
+    let style = current_style();
+    match (&leaf.rule, &leaf.keep_reason) {
+        (_, Some(reason)) => {
+            let marker = format!("{}kept ({})", style.symbol("✓ ", ""), reason);
+            format!(" {}", style.dimmed(&marker))
+        }
+        (Some(rule), None) if show_deleted => {
+            let marker = format!("{}deleted ({})", style.symbol("✗ ", ""), rule);
+            format!(" {}", style.red(&marker))
+        }
+        _ => String::new(),
+    }
+}
+
+/// Cumulative totals for everything beneath a directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirTotals {
+    pub deletion_size: u64,
+    pub file_count: usize,
+    pub deletion_count: usize,
+    pub dir_count: usize,
+}
+
+impl DirTotals {
+    pub fn add(&mut self, other: &DirTotals) {
+        self.deletion_size += other.deletion_size;
+        self.file_count += other.file_count;
+        self.deletion_count += other.deletion_count;
+        self.dir_count += other.dir_count;
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DirTreeLeaf {
     pub key: PathBuf,
     pub depth: usize,
-    pub is_last: bool,
+    pub is_dir: bool,
+    // Size in bytes and last modification time, as recorded while scanning.
+    // For directories, the size covers every file beneath them.
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    // Only populated for directories.
+    pub totals: DirTotals,
+    // The rule that scheduled this file for deletion, if any.
+    pub rule: Option<DeletionRule>,
+    // Why this file is being kept. Only set when kept files are tracked.
+    pub keep_reason: Option<KeepReason>,
 }
 
 impl DirTreeLeaf {
@@ -49,24 +163,46 @@ impl DirTreeLeaf {
         DirTreeLeaf {
             key: root_folder,
             depth: 0,
-            is_last: true,
+            is_dir: true,
+            ..Default::default()
         }
     }
+
+    /// Whether this leaf is a file that's due to be deleted.
+    pub fn is_scheduled_file(&self) -> bool {
+        !self.is_dir && self.keep_reason.is_none()
+    }
+}
+
+impl DirTreeLeaf {
+    /// How the leaf is labelled in a tree. The root is shown as scanned, everything
+    /// else by its name alone unless `full_path` is set, since the tree branches
+    /// already show where it lives.
+    pub fn tree_label(&self, full_path: bool) -> String {
+        let name = match (full_path, self.depth, self.key.file_name()) {
+            (true, _, _) | (_, 0, _) | (_, _, None) => escape_path(&self.key),
+            (_, _, Some(file_name)) => escape_path(Path::new(file_name)),
+        };
+
+        // Keep names containing newlines or tabs from breaking up the tree.
+        name.chars()
+            .map(|character| match character.is_control() {
+                true => character.escape_default().to_string(),
+                false => character.to_string(),
+            })
+            .collect()
+    }
 }
 
 impl fmt::Display for DirTreeLeaf {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.key.to_str() {
-            Some(path_str) => write!(f, "{}", path_str),
-            None => write!(f, "<invalid utf8 path>"),
-        }
+        write!(f, "{}", self.tree_label(false))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     // #[test]
     // fn test_skip_leaf_with_file_and_display_files_true() {
@@ -98,38 +234,171 @@ mod tests {
     fn test_skip_leaf_with_directory_regardless_of_display_files() {
         let options_with_true = DirTreeOptions {
             display_files: true,
-            file_size: None,
+            ..Default::default()
         };
         let options_with_false = DirTreeOptions {
             display_files: false,
-            file_size: None,
+            ..Default::default()
         };
 
-        let directory_path = PathBuf::from("some_directory");
-        assert!(!options_with_true.should_skip_file_leaf(&directory_path));
-        assert!(!options_with_false.should_skip_file_leaf(&directory_path));
+        assert!(!options_with_true.should_skip_leaf(true));
+        assert!(!options_with_false.should_skip_leaf(true));
+        assert!(!options_with_true.should_skip_leaf(false));
+        assert!(options_with_false.should_skip_leaf(false));
+    }
+
+    #[test]
+    fn test_is_beyond_max_depth() {
+        let unlimited = DirTreeOptions::default();
+        let limited = DirTreeOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+
+        assert!(!unlimited.is_beyond_max_depth(100));
+        assert!(!limited.is_beyond_max_depth(2));
+        assert!(limited.is_beyond_max_depth(3));
+    }
+
+    fn directory_leaf() -> DirTreeLeaf {
+        DirTreeLeaf {
+            key: PathBuf::from("some_directory"),
+            is_dir: true,
+            size: 4096,
+            totals: DirTotals {
+                deletion_size: 1024,
+                file_count: 3,
+                deletion_count: 1,
+                dir_count: 2,
+            },
+            ..Default::default()
+        }
     }
 
     #[test]
     fn test_get_tree_suffix_str_with_no_suffix() {
+        let options = DirTreeOptions::default();
+
+        assert_eq!(
+            options.get_tree_suffix_str(&directory_leaf()),
+            String::new()
+        );
+    }
+
+    #[test]
+    fn test_get_tree_suffix_str_with_suffix() {
         let options = DirTreeOptions {
-            display_files: true,
-            file_size: None,
+            show_sizes: true,
+            ..Default::default()
+        };
+        let file_leaf = DirTreeLeaf {
+            key: PathBuf::from("some_file.txt"),
+            size: 1024,
+            ..Default::default()
         };
 
-        assert_eq!(options.get_tree_suffix_str(), String::new());
+        assert_eq!(options.get_tree_suffix_str(&file_leaf), " - 1.00 KB");
+        assert_eq!(
+            options.get_tree_suffix_str(&directory_leaf()),
+            " - 4.00 KB (3 files, 2 dirs)"
+        );
     }
 
     #[test]
-    fn test_get_tree_suffix_str_with_suffix() {
+    fn test_get_tree_suffix_str_with_scheduled_totals() {
         let options = DirTreeOptions {
-            display_files: true,
-            file_size: Some(1024),
+            show_sizes: true,
+            show_scheduled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.get_tree_suffix_str(&directory_leaf()),
+            " - 1.00 KB scheduled of 4.00 KB total (1 of 3 files, 2 dirs)"
+        );
+    }
+
+    #[test]
+    fn test_display_shows_root_path_and_child_names() {
+        let root = DirTreeLeaf::new_root(PathBuf::from("/tmp/root"));
+        let child = DirTreeLeaf {
+            key: PathBuf::from("/tmp/root/sub/notes.txt"),
+            depth: 2,
+            ..Default::default()
+        };
+        let awkward = DirTreeLeaf {
+            key: PathBuf::from("/tmp/root/two\nlines.txt"),
+            depth: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(root.to_string(), "/tmp/root");
+        assert_eq!(child.to_string(), "notes.txt");
+        assert_eq!(awkward.to_string(), "two\\nlines.txt");
+        assert_eq!(child.tree_label(true), "/tmp/root/sub/notes.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_display_escapes_invalid_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let leaf = DirTreeLeaf {
+            key: PathBuf::from("root").join(OsStr::from_bytes(b"bad\xffname")),
+            depth: 1,
+            ..Default::default()
+        };
+        assert_eq!(leaf.to_string(), "bad\\xFFname");
+    }
+
+    #[test]
+    fn test_get_tree_suffix_str_with_status_markers() {
+        let options = DirTreeOptions {
+            show_status: true,
+            ..Default::default()
+        };
+        let deleted = DirTreeLeaf {
+            key: PathBuf::from("app.log"),
+            rule: Some(DeletionRule::MatchedExtension("log".to_string())),
+            ..Default::default()
+        };
+        let kept = DirTreeLeaf {
+            key: PathBuf::from("main.rs"),
+            keep_reason: Some(KeepReason::KeptExtension("rs".to_string())),
+            ..Default::default()
         };
 
         assert_eq!(
-            options.get_tree_suffix_str(),
-            format!(" - \x1b[1m{}\x1b[0m", "1.00 KB")
+            options.get_tree_suffix_str(&deleted),
+            " ✗ deleted (extension .log)"
         );
+        assert_eq!(
+            options.get_tree_suffix_str(&kept),
+            " ✓ kept (extension .rs is kept)"
+        );
+        assert_eq!(options.get_tree_suffix_str(&directory_leaf()), "");
+    }
+
+    #[test]
+    fn test_size_trees_only_mark_kept_files() {
+        let options = DirTreeOptions {
+            show_status: true,
+            show_deleted: false,
+            ..Default::default()
+        };
+        let matched = DirTreeLeaf {
+            key: PathBuf::from("app.log"),
+            rule: Some(DeletionRule::MatchedExtension("log".to_string())),
+            ..Default::default()
+        };
+        let kept = DirTreeLeaf {
+            key: PathBuf::from("main.rs"),
+            keep_reason: Some(KeepReason::NotListed("rs".to_string())),
+            ..Default::default()
+        };
+
+        assert_eq!(options.get_tree_suffix_str(&matched), "");
+        assert!(options.get_tree_suffix_str(&kept).contains("kept"));
     }
 }
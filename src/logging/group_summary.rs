@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use super::style::current_style;
+use super::{DASHED_LINE, LINE};
+use crate::cleaning::track_files_for_deletion::DeletionMetaData;
+use crate::configs::config::PathConfig;
+use crate::utils::{format_count, format_size};
+
+// Folders given by path rather than through a config group are listed under this name.
+const UNGROUPED_LABEL: &str = "(no group)";
+
+/// The combined totals for every scanned folder in a config group.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GroupTotals {
+    pub directories: usize,
+    pub files: usize,
+    pub total_size: u64,
+    pub reclaimable_size: u64,
+}
+
+impl GroupTotals {
+    fn add(&mut self, other: &GroupTotals) {
+        self.directories += other.directories;
+        self.files += other.files;
+        self.total_size += other.total_size;
+        self.reclaimable_size += other.reclaimable_size;
+    }
+}
+
+/// Totals for each config group scanned in a single run, in group name order.
+#[derive(Debug, Default)]
+pub struct GroupSummary {
+    groups: BTreeMap<String, GroupTotals>,
+}
+
+impl GroupSummary {
+    pub fn new() -> GroupSummary {
+        GroupSummary::default()
+    }
+
+    /// Adds a scanned folder to the totals of the group it belongs to.
+    pub fn add_folder(&mut self, config: &PathConfig, deletion_metadata: &DeletionMetaData) {
+        let group = config.group.as_deref().unwrap_or(UNGROUPED_LABEL);
+        let folder = GroupTotals {
+            directories: 1,
+            files: deletion_metadata.file_count,
+            total_size: deletion_metadata.folder_size,
+            reclaimable_size: deletion_metadata.deletion_size,
+        };
+        self.groups
+            .entry(group.to_string())
+            .or_default()
+            .add(&folder);
+    }
+
+    pub fn grand_total(&self) -> GroupTotals {
+        let mut total = GroupTotals::default();
+        self.groups.values().for_each(|group| total.add(group));
+        total
+    }
+
+    pub fn generate_text(&self) -> String {
+        let style = current_style();
+        let header = format!(
+            "  {:<16} {:>11} {:>9} {:>12} {:>12}",
+            "Group", "Directories", "Files", "Total", "Reclaimable"
+        );
+
+        let mut lines = vec![
+            LINE.to_string(),
+            style.heading("📊", "Summary by group"),
+            DASHED_LINE.to_string(),
+            style.bold(&header),
+        ];
+        lines.extend(
+            self.groups
+                .iter()
+                .map(|(group, totals)| format_row(group, totals)),
+        );
+        lines.push(DASHED_LINE.to_string());
+        lines.push(style.bold(&format_row("Total", &self.grand_total())));
+        lines.push(LINE.to_string());
+        lines.join("\n")
+    }
+}
+
+fn format_row(label: &str, totals: &GroupTotals) -> String {
+    format!(
+        "  {:<16} {:>11} {:>9} {:>12} {:>12}",
+        label,
+        format_count(totals.directories),
+        format_count(totals.files),
+        format_size(totals.total_size),
+        format_size(totals.reclaimable_size)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn scanned_folder(
+        group: Option<&str>,
+        files: usize,
+        size: u64,
+    ) -> (PathConfig, DeletionMetaData) {
+        let mut config = PathConfig::new(PathBuf::from("folder"), true);
+        config.group = group.map(str::to_string);
+
+        let mut metadata = DeletionMetaData::from_root_folder(Path::new("missing"));
+        metadata.folder_size = size;
+        metadata.deletion_size = size / 2;
+        metadata.file_count = files;
+        (config, metadata)
+    }
+
+    #[test]
+    fn test_groups_listed_in_name_order_with_a_grand_total() {
+        let mut summary = GroupSummary::new();
+        for (group, files, size) in [
+            (Some("work"), 3, 300),
+            (Some("downloads"), 1, 100),
+            (Some("work"), 2, 200),
+            (None, 1, 10),
+        ] {
+            let (config, metadata) = scanned_folder(group, files, size);
+            summary.add_folder(&config, &metadata);
+        }
+
+        let groups: Vec<&String> = summary.groups.keys().collect();
+        assert_eq!(groups, vec!["(no group)", "downloads", "work"]);
+        assert_eq!(
+            summary.groups["work"],
+            GroupTotals {
+                directories: 2,
+                files: 5,
+                total_size: 500,
+                reclaimable_size: 250,
+            }
+        );
+        assert_eq!(summary.grand_total().directories, 4);
+        assert_eq!(summary.grand_total().reclaimable_size, 305);
+
+        let text = summary.generate_text();
+        assert!(text.contains("Summary by group"));
+        assert!(text.contains("  Total                      4         7"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+use serde_derive::Serialize;
+
+use super::nested_tree::{build_nested_tree, TreeNode};
+use super::process_directory_tree::FileSystemStack;
+use crate::cleaning::track_files_for_deletion::DeletionMetaData;
+use crate::configs::config::PathConfig;
+use crate::utils::escape_path;
+
+// The page layout, styles and scripts. Everything is inlined so the
+// report can be opened offline and attached to tickets as a single file.
+const REPORT_TEMPLATE: &str = include_str!("assets/report.html");
+const REPORT_DATA_PLACEHOLDER: &str = "/*REPORT_DATA*/";
+
+#[derive(Serialize)]
+struct HtmlTreeNode {
+    name: String,
+    size: u64,
+    files: usize,
+    dir: bool,
+    scheduled: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<HtmlTreeNode>,
+}
+
+impl HtmlTreeNode {
+    fn from_tree(node: &TreeNode, name: String) -> HtmlTreeNode {
+        HtmlTreeNode {
+            name,
+            size: node.total_size,
+            files: node.file_count,
+            dir: node.is_dir,
+            scheduled: node.leaf.is_scheduled_file(),
+            children: node
+                .children
+                .iter()
+                .map(|child| HtmlTreeNode::from_tree(child, child.name()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+struct ExtensionRow {
+    extension: String,
+    count: usize,
+    size: u64,
+}
+
+/// The data needed to render a single scanned folder in the HTML report.
+#[derive(Serialize)]
+pub struct HtmlReportFolder {
+    directory: String,
+    group: Option<String>,
+    folder_size: u64,
+    deletion_size: u64,
+    file_count: usize,
+    dir_count: usize,
+    extensions: Vec<ExtensionRow>,
+    tree: Option<HtmlTreeNode>,
+}
+
+impl HtmlReportFolder {
+    pub fn new(
+        config: &PathConfig,
+        deletion_metadata: &DeletionMetaData,
+        stack: &FileSystemStack,
+    ) -> HtmlReportFolder {
+        let directory = escape_path(&config.directory);
+        let tree =
+            build_nested_tree(stack).map(|root| HtmlTreeNode::from_tree(&root, directory.clone()));
+
+        HtmlReportFolder {
+            directory,
+            group: config.group.clone(),
+            folder_size: deletion_metadata.folder_size,
+            deletion_size: deletion_metadata.deletion_size,
+            file_count: deletion_metadata.file_count,
+            dir_count: deletion_metadata.dir_count,
+            extensions: summarise_extensions(stack),
+            tree,
+        }
+    }
+}
+
+fn summarise_extensions(stack: &FileSystemStack) -> Vec<ExtensionRow> {
+    let mut extensions: BTreeMap<String, ExtensionRow> = BTreeMap::new();
+
+    for leaf in stack.iter().filter(|leaf| leaf.key.is_file()) {
+        let extension = leaf
+            .key
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let row = extensions.entry(extension.clone()).or_default();
+        row.extension = extension;
+        row.count += 1;
+        row.size += leaf.size;
+    }
+
+    let mut rows: Vec<ExtensionRow> = extensions.into_values().collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.size));
+    rows
+}
+
+#[derive(Serialize)]
+struct HtmlReportData<'a> {
+    generated: DateTime<Local>,
+    folders: &'a [HtmlReportFolder],
+}
+
+/// Renders the scanned folders as a self-contained HTML page, with a collapsible
+/// tree, a treemap of the space used and a breakdown by file extension.
+pub fn generate_html_report(folders: &[HtmlReportFolder]) -> String {
+    let data = HtmlReportData {
+        generated: Local::now(),
+        folders,
+    };
+    let json = serde_json::to_string(&data).expect("Report data is always serialisable");
+
+    // Stop file names from closing the script tag the data is embedded in.
+    let json = json.replace("</", "<\\/");
+    REPORT_TEMPLATE.replace(REPORT_DATA_PLACEHOLDER, &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::mark_for_deletion::KeepReason;
+    use crate::logging::folder_tree_helpers::DirTreeLeaf;
+    use std::collections::VecDeque;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_generate_html_report_embeds_escaped_data() {
+        let mut config = PathConfig::new(PathBuf::from("root"), true);
+        config.group = Some("</script>".to_string());
+        let mut stack: FileSystemStack = VecDeque::new();
+        stack.push_back(DirTreeLeaf::new_root(PathBuf::from("root")));
+        stack.push_back(DirTreeLeaf {
+            key: PathBuf::from("root/notes.txt"),
+            depth: 1,
+            size: 42,
+            ..Default::default()
+        });
+        stack.push_back(DirTreeLeaf {
+            key: PathBuf::from("root/plan.md"),
+            depth: 1,
+            keep_reason: Some(KeepReason::NotListed("md".to_string())),
+            ..Default::default()
+        });
+
+        let metadata = DeletionMetaData::from_root_folder(&config.directory);
+        let folder = HtmlReportFolder::new(&config, &metadata, &stack);
+        let html = generate_html_report(&[folder]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains(REPORT_DATA_PLACEHOLDER));
+        assert!(html.contains(r#""group":"<\/script>""#));
+        assert!(
+            html.contains(r#""name":"notes.txt","size":42,"files":1,"dir":false,"scheduled":true"#)
+        );
+        assert!(
+            html.contains(r#""name":"plan.md","size":0,"files":1,"dir":false,"scheduled":false"#)
+        );
+        assert!(!html.contains("<script src="));
+        assert!(!html.contains("<link "));
+    }
+}
@@ -0,0 +1,237 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::{Path, PathBuf};
+
+use super::process_directory_tree::FileSystemStack;
+use super::style::current_style;
+use super::{DASHED_LINE, LINE};
+use crate::utils::format_size;
+
+/// A file or directory, alongside its (cumulative) size in bytes.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RankedEntry {
+    // Size comes first so entries are ordered by size, then path.
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+/// The largest files and directories found in a scanned tree.
+pub struct LargestEntries {
+    pub root: PathBuf,
+    pub root_size: u64,
+    pub files: Vec<RankedEntry>,
+    pub directories: Vec<RankedEntry>,
+}
+
+// Keeps hold of the `limit` largest entries pushed into it.
+struct TopN {
+    limit: usize,
+    heap: BinaryHeap<Reverse<RankedEntry>>,
+}
+
+impl TopN {
+    fn new(limit: usize) -> TopN {
+        TopN {
+            limit,
+            heap: BinaryHeap::with_capacity(limit + 1),
+        }
+    }
+
+    fn push(&mut self, entry: RankedEntry) {
+        if self.limit == 0 {
+            return;
+        }
+        self.heap.push(Reverse(entry));
+        if self.heap.len() > self.limit {
+            self.heap.pop();
+        }
+    }
+
+    fn into_sorted_vec(self) -> Vec<RankedEntry> {
+        // Sorting the reversed entries ascending leaves the largest first.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(entry)| entry)
+            .collect()
+    }
+}
+
+/// Finds the `limit` largest files and directories in a single pass over the stack.
+///
+/// Only files that are due to be deleted are ranked, so any kept files tracked for
+/// the tree are left out. Directory sizes are cumulative, covering every ranked file
+/// beneath them. The root folder itself is excluded from the directory ranking.
+pub fn find_largest_entries(stack: &FileSystemStack, limit: usize) -> LargestEntries {
+    let mut files = TopN::new(limit);
+    let mut directories = TopN::new(limit);
+    // The chain of directories leading to the current leaf, with their running totals.
+    let mut open_directories: Vec<RankedEntry> = Vec::new();
+    let mut root = PathBuf::new();
+    let mut root_size = 0;
+
+    let mut close_directory = |open: &mut Vec<RankedEntry>| {
+        let directory = open.pop().expect("Only called while directories are open");
+        match open.last_mut() {
+            Some(parent) => {
+                parent.size += directory.size;
+                directories.push(directory);
+            }
+            None => {
+                root_size = directory.size;
+                root = directory.path;
+            }
+        }
+    };
+
+    for leaf in stack.iter() {
+        while open_directories.len() > leaf.depth {
+            close_directory(&mut open_directories);
+        }
+
+        if leaf.is_dir {
+            open_directories.push(RankedEntry {
+                size: 0,
+                path: leaf.key.clone(),
+            });
+        } else if leaf.is_scheduled_file() {
+            if let Some(parent) = open_directories.last_mut() {
+                parent.size += leaf.size;
+            }
+            files.push(RankedEntry {
+                size: leaf.size,
+                path: leaf.key.clone(),
+            });
+        }
+    }
+
+    while !open_directories.is_empty() {
+        close_directory(&mut open_directories);
+    }
+
+    LargestEntries {
+        root,
+        root_size,
+        files: files.into_sorted_vec(),
+        directories: directories.into_sorted_vec(),
+    }
+}
+
+fn format_ranked_entry(rank: usize, entry: &RankedEntry, root: &Path, root_size: u64) -> String {
+    let share = match root_size {
+        0 => 0.0,
+        _ => entry.size as f64 * 100.0 / root_size as f64,
+    };
+    let path = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+
+    format!(
+        "{:>4}. {:>12} {:>6.1}%  {}",
+        rank,
+        format_size(entry.size),
+        share,
+        path.display()
+    )
+}
+
+fn format_ranking(
+    title: &str,
+    entries: &[RankedEntry],
+    root: &Path,
+    root_size: u64,
+) -> Vec<String> {
+    let mut lines = vec![LINE.to_string(), title.to_string(), DASHED_LINE.to_string()];
+    if entries.is_empty() {
+        lines.push("  Nothing to show.".to_string());
+    }
+    for (index, entry) in entries.iter().enumerate() {
+        lines.push(format_ranked_entry(index + 1, entry, root, root_size));
+    }
+    lines
+}
+
+pub fn generate_largest_entries_text(entries: &LargestEntries) -> String {
+    let style = current_style();
+    let mut text = format_ranking(
+        &style.heading("🏆", "Largest files"),
+        &entries.files,
+        &entries.root,
+        entries.root_size,
+    );
+    text.extend(format_ranking(
+        &style.heading("🏆", "Largest directories"),
+        &entries.directories,
+        &entries.root,
+        entries.root_size,
+    ));
+    text.push(LINE.to_string());
+
+    text.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::mark_for_deletion::KeepReason;
+    use crate::logging::folder_tree_helpers::DirTreeLeaf;
+    use std::collections::VecDeque;
+
+    fn build_stack() -> FileSystemStack {
+        let leaves = vec![
+            ("root", 0, true, 0),
+            ("root/small.txt", 1, false, 5),
+            ("root/media", 1, true, 0),
+            ("root/media/film.mp4", 2, false, 500),
+            ("root/media/clips", 2, true, 0),
+            ("root/media/clips/a.mp4", 3, false, 100),
+            ("root/logs", 1, true, 0),
+            ("root/logs/app.log", 2, false, 200),
+        ];
+
+        leaves
+            .into_iter()
+            .map(|(key, depth, is_dir, size)| DirTreeLeaf {
+                key: PathBuf::from(key),
+                depth,
+                is_dir,
+                size,
+                ..Default::default()
+            })
+            .collect::<VecDeque<DirTreeLeaf>>()
+    }
+
+    #[test]
+    fn test_find_largest_entries() {
+        let largest = find_largest_entries(&build_stack(), 2);
+
+        assert_eq!(largest.root, PathBuf::from("root"));
+        assert_eq!(largest.root_size, 805);
+
+        let file_sizes: Vec<u64> = largest.files.iter().map(|e| e.size).collect();
+        assert_eq!(file_sizes, vec![500, 200]);
+
+        let directories: Vec<(&str, u64)> = largest
+            .directories
+            .iter()
+            .map(|e| (e.path.to_str().unwrap(), e.size))
+            .collect();
+        assert_eq!(directories, vec![("root/media", 600), ("root/logs", 200)]);
+    }
+
+    #[test]
+    fn test_find_largest_entries_skips_kept_files() {
+        let mut stack = build_stack();
+        stack[3].keep_reason = Some(KeepReason::TooNew(7));
+
+        let largest = find_largest_entries(&stack, 1);
+        assert_eq!(largest.root_size, 305);
+        assert_eq!(largest.files[0].path, PathBuf::from("root/logs/app.log"));
+    }
+
+    #[test]
+    fn test_generate_largest_entries_text() {
+        let text = generate_largest_entries_text(&find_largest_entries(&build_stack(), 1));
+
+        assert!(text.contains("   1.     500.00 B   62.1%  media/film.mp4"));
+        assert!(text.contains("   1.     600.00 B   74.5%  media\n"));
+    }
+}
@@ -0,0 +1,298 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use serde_derive::Serialize;
+
+use super::process_directory_tree::FileSystemStack;
+use crate::cleaning::track_files_for_deletion::DeletionMetaData;
+use crate::cleaning::DeletionSummary;
+use crate::configs::config::PathConfig;
+use crate::utils::escape_path;
+
+/// Version of the JSON and NDJSON schemas. Bump this whenever a field is
+/// renamed, removed or changes meaning, so downstream tools can detect it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+fn to_local_time(time: SystemTime) -> DateTime<Local> {
+    time.into()
+}
+
+#[derive(Serialize)]
+struct MetadataRecord {
+    folder_size: u64,
+    deletion_size: u64,
+    file_count: usize,
+    dir_count: usize,
+    last_modified: DateTime<Local>,
+}
+
+#[derive(Serialize)]
+struct FilterRecord {
+    extensions_to_delete: Option<Vec<String>>,
+    extensions_to_keep: Option<Vec<String>>,
+    names_to_delete: Option<Vec<String>>,
+    recursive: bool,
+    delete_hidden: bool,
+    older_than_days: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct CandidateRecord {
+    path: String,
+    size: u64,
+    modified: Option<DateTime<Local>>,
+}
+
+#[derive(Serialize)]
+struct FailedDeletionRecord {
+    path: String,
+    error: String,
+}
+
+#[derive(Serialize)]
+struct DeletionRecord {
+    deleted_files: usize,
+    deleted_size: u64,
+    deleted: Vec<String>,
+    failed: Vec<FailedDeletionRecord>,
+    removed_dirs: Vec<String>,
+}
+
+/// A machine-readable description of a single scanned folder.
+#[derive(Serialize)]
+pub struct FolderReport {
+    group: Option<String>,
+    directory: String,
+    metadata: MetadataRecord,
+    filters: FilterRecord,
+    candidates: Vec<CandidateRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletion: Option<DeletionRecord>,
+}
+
+impl FolderReport {
+    pub fn new(
+        config: &PathConfig,
+        deletion_metadata: &DeletionMetaData,
+        candidates: &FileSystemStack,
+    ) -> FolderReport {
+        FolderReport {
+            group: config.group.clone(),
+            directory: escape_path(&config.directory),
+            metadata: MetadataRecord {
+                folder_size: deletion_metadata.folder_size,
+                deletion_size: deletion_metadata.deletion_size,
+                file_count: deletion_metadata.file_count,
+                dir_count: deletion_metadata.dir_count,
+                last_modified: to_local_time(deletion_metadata.last_modified_time),
+            },
+            filters: FilterRecord {
+                extensions_to_delete: config.extensions_to_delete.clone(),
+                extensions_to_keep: config.extensions_to_keep.clone(),
+                names_to_delete: config.names_to_delete.clone(),
+                recursive: config.recursive,
+                delete_hidden: config.delete_hidden,
+                older_than_days: config.older_than_days,
+            },
+            candidates: candidates
+                .iter()
+                .filter(|leaf| leaf.is_scheduled_file())
+                .map(|leaf| CandidateRecord {
+                    path: escape_path(&leaf.key),
+                    size: leaf.size,
+                    modified: leaf.modified.map(to_local_time),
+                })
+                .collect(),
+            deletion: None,
+        }
+    }
+
+    /// Attaches the result of a deletion run to the report.
+    pub fn set_deletion(&mut self, summary: &DeletionSummary) {
+        self.deletion = Some(DeletionRecord {
+            deleted_files: summary.deleted.len(),
+            deleted_size: summary.deleted_size,
+            deleted: summary.deleted.iter().map(|p| escape_path(p)).collect(),
+            failed: summary
+                .failed
+                .iter()
+                .map(|(path, error)| FailedDeletionRecord {
+                    path: escape_path(path),
+                    error: error.to_string(),
+                })
+                .collect(),
+            removed_dirs: summary
+                .removed_dirs
+                .iter()
+                .map(|p| escape_path(p))
+                .collect(),
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    command: &'a str,
+    folders: &'a [FolderReport],
+}
+
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum NdjsonRecord<'a> {
+    Folder {
+        schema_version: u32,
+        command: &'a str,
+        group: &'a Option<String>,
+        directory: &'a str,
+        metadata: &'a MetadataRecord,
+        filters: &'a FilterRecord,
+    },
+    File {
+        schema_version: u32,
+        group: &'a Option<String>,
+        directory: &'a str,
+        #[serde(flatten)]
+        candidate: &'a CandidateRecord,
+    },
+    Deletion {
+        schema_version: u32,
+        group: &'a Option<String>,
+        directory: &'a str,
+        #[serde(flatten)]
+        deletion: &'a DeletionRecord,
+    },
+}
+
+/// Renders every folder report as a single, pretty-printed JSON document.
+pub fn generate_json_report(command: &str, folders: &[FolderReport]) -> String {
+    let report = JsonReport {
+        schema_version: SCHEMA_VERSION,
+        command,
+        folders,
+    };
+    serde_json::to_string_pretty(&report).expect("Folder reports are always serialisable")
+}
+
+/// Renders a folder report as newline delimited JSON. The first record describes the
+/// folder, followed by one record per candidate file and, if files were deleted,
+/// a final record describing the outcome.
+pub fn generate_ndjson_records(command: &str, folder: &FolderReport) -> String {
+    let mut records = vec![NdjsonRecord::Folder {
+        schema_version: SCHEMA_VERSION,
+        command,
+        group: &folder.group,
+        directory: &folder.directory,
+        metadata: &folder.metadata,
+        filters: &folder.filters,
+    }];
+
+    records.extend(
+        folder
+            .candidates
+            .iter()
+            .map(|candidate| NdjsonRecord::File {
+                schema_version: SCHEMA_VERSION,
+                group: &folder.group,
+                directory: &folder.directory,
+                candidate,
+            }),
+    );
+
+    if let Some(deletion) = &folder.deletion {
+        records.push(NdjsonRecord::Deletion {
+            schema_version: SCHEMA_VERSION,
+            group: &folder.group,
+            directory: &folder.directory,
+            deletion,
+        });
+    }
+
+    records
+        .iter()
+        .map(|record| {
+            serde_json::to_string(record).expect("NDJSON records are always serialisable")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::folder_tree_helpers::DirTreeLeaf;
+    use serde_json::Value;
+    use std::collections::VecDeque;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn build_report() -> (tempfile::TempDir, FolderReport) {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("notes.tmp");
+        let mut file = File::create(&file_path).unwrap();
+        write!(file, "twelve bytes").unwrap();
+
+        let mut config = PathConfig::new(dir.path().to_path_buf(), true);
+        config.group = Some("downloads".to_string());
+        config.extensions_to_delete = Some(vec!["tmp".to_string()]);
+
+        let mut stack: FileSystemStack = VecDeque::new();
+        stack.push_back(DirTreeLeaf::new_root(dir.path().to_path_buf()));
+        stack.push_back(DirTreeLeaf {
+            key: file_path,
+            depth: 1,
+            size: 12,
+            modified: Some(SystemTime::now()),
+            ..Default::default()
+        });
+
+        let mut metadata = DeletionMetaData::from_root_folder(dir.path());
+        metadata.folder_size = 12;
+        metadata.deletion_size = 12;
+        metadata.file_count = 1;
+
+        let report = FolderReport::new(&config, &metadata, &stack);
+        (dir, report)
+    }
+
+    #[test]
+    fn test_json_report_contains_versioned_folder_data() {
+        let (_dir, report) = build_report();
+        let json: Value = serde_json::from_str(&generate_json_report("size", &[report])).unwrap();
+
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["command"], "size");
+
+        let folder = &json["folders"][0];
+        assert_eq!(folder["group"], "downloads");
+        assert_eq!(folder["metadata"]["deletion_size"], 12);
+        assert_eq!(folder["filters"]["extensions_to_delete"][0], "tmp");
+        assert_eq!(folder["candidates"].as_array().unwrap().len(), 1);
+        assert_eq!(folder["candidates"][0]["size"], 12);
+        assert!(folder.get("deletion").is_none());
+    }
+
+    #[test]
+    fn test_ndjson_emits_one_record_per_line() {
+        let (_dir, mut report) = build_report();
+        report.set_deletion(&DeletionSummary::default());
+
+        let output = generate_ndjson_records("clean", &report);
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let kinds: Vec<&str> = records
+            .iter()
+            .map(|r| r["record"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, vec!["folder", "file", "deletion"]);
+        assert!(records
+            .iter()
+            .all(|r| r["schema_version"] == SCHEMA_VERSION));
+        assert_eq!(records[1]["size"], 12);
+        assert_eq!(records[2]["deleted_files"], 0);
+    }
+}
@@ -1,12 +1,27 @@
+pub mod config_listing;
+pub mod csv_export;
 mod deletion_overview;
 pub mod folder_tree_helpers;
+pub mod group_summary;
+pub mod html_report;
+mod largest_entries;
+pub mod machine_output;
+pub mod nested_tree;
+pub mod preset_listing;
 pub mod process_directory_tree;
+pub mod style;
+pub mod validation_report;
 
 use crate::{cleaning::track_files_for_deletion::DeletionMetaData, configs::config::PathConfig};
 use deletion_overview::{generate_deletion_overview_text, generate_size_overview_text};
 use folder_tree_helpers::DirTreeOptions;
+use largest_entries::{find_largest_entries, generate_largest_entries_text};
 use process_directory_tree::{process_folder_tree_stack, FileSystemStack};
 
+// Rules framing the headings and sections of the text reports.
+const DASHED_LINE: &str = "---------------------------------------------------------";
+const LINE: &str = "=========================================================";
+
 pub enum TextOverviewType {
     Deletion,
     Size,
@@ -16,7 +31,7 @@ impl TextOverviewType {
     pub fn generate_text(
         &self,
         config: &PathConfig,
-        deletion_metadata: DeletionMetaData,
+        deletion_metadata: &DeletionMetaData,
     ) -> String {
         match self {
             TextOverviewType::Deletion => {
@@ -25,6 +40,14 @@ impl TextOverviewType {
             TextOverviewType::Size => generate_size_overview_text(config, deletion_metadata),
         }
     }
+
+    /// The name of the subcommand that produces this overview.
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            TextOverviewType::Deletion => "clean",
+            TextOverviewType::Size => "size",
+        }
+    }
 }
 
 /// Prints a directory tree for a given file system stack.
@@ -32,9 +55,20 @@ impl TextOverviewType {
 /// # Arguments
 ///
 /// * `directory_queue` - A `FileSystemStack` that represents the file system stack for either
-/// deletion or printing.
-pub fn print_directory_tree(directory_queue: FileSystemStack) {
-    let options = DirTreeOptions::default();
-    let deletion_tree = process_folder_tree_stack(directory_queue, &options);
+///   deletion or printing.
+/// * `options` - How the tree should be laid out, e.g. its depth and ordering.
+pub fn print_directory_tree(directory_queue: &FileSystemStack, options: &DirTreeOptions) {
+    let deletion_tree = process_folder_tree_stack(directory_queue, options);
     println!("{}", deletion_tree);
 }
+
+/// Prints the largest files and directories in a given file system stack.
+///
+/// # Arguments
+///
+/// * `directory_queue` - The `FileSystemStack` produced by scanning a folder.
+/// * `limit` - How many files and directories to list.
+pub fn print_largest_entries(directory_queue: &FileSystemStack, limit: usize) {
+    let largest_entries = find_largest_entries(directory_queue, limit);
+    println!("{}", generate_largest_entries_text(&largest_entries));
+}
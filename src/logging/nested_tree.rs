@@ -0,0 +1,132 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use super::folder_tree_helpers::DirTreeLeaf;
+use super::process_directory_tree::FileSystemStack;
+
+/// A node in a directory tree rebuilt from the flat, depth-first `FileSystemStack`.
+#[derive(Debug)]
+pub struct TreeNode {
+    pub leaf: DirTreeLeaf,
+    pub is_dir: bool,
+    // Cumulative size and file count of everything beneath this node.
+    pub total_size: u64,
+    pub file_count: usize,
+    // The most recent modification time of anything beneath this node.
+    pub latest_modified: Option<SystemTime>,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(leaf: DirTreeLeaf) -> TreeNode {
+        let is_dir = leaf.is_dir;
+        let latest_modified = leaf.modified;
+        TreeNode {
+            leaf,
+            is_dir,
+            total_size: 0,
+            file_count: 0,
+            latest_modified,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.leaf
+            .key
+            .file_name()
+            .map(|name| Path::new(name).display().to_string())
+            .unwrap_or_else(|| self.leaf.key.display().to_string())
+    }
+
+    fn attach(&mut self, child: TreeNode) {
+        self.total_size += child.total_size;
+        self.file_count += child.file_count;
+        self.latest_modified = self.latest_modified.max(child.latest_modified);
+        self.children.push(child);
+    }
+
+    fn close(mut self) -> TreeNode {
+        // Anything with children is a directory, even if it's no longer on disk.
+        self.is_dir = self.is_dir || !self.children.is_empty();
+        if !self.is_dir {
+            self.total_size = self.leaf.size;
+            self.file_count = 1;
+        }
+        self
+    }
+}
+
+/// Rebuilds the nested directory structure described by a depth-first `FileSystemStack`,
+/// rolling file sizes and counts up to every directory along the way.
+///
+/// Returns `None` if the stack is empty.
+pub fn build_nested_tree(stack: &FileSystemStack) -> Option<TreeNode> {
+    // The chain of directories leading to the most recently visited leaf.
+    let mut open_nodes: Vec<TreeNode> = Vec::new();
+
+    for leaf in stack.iter() {
+        close_nodes_deeper_than(&mut open_nodes, leaf.depth);
+        open_nodes.push(TreeNode::new(leaf.clone()));
+    }
+
+    close_nodes_deeper_than(&mut open_nodes, 1);
+    open_nodes.pop().map(TreeNode::close)
+}
+
+fn close_nodes_deeper_than(open_nodes: &mut Vec<TreeNode>, depth: usize) {
+    while open_nodes.len() > depth.max(1) {
+        let node = open_nodes.pop().unwrap().close();
+        match open_nodes.last_mut() {
+            Some(parent) => parent.attach(node),
+            None => unreachable!("The root node is never closed early"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_build_nested_tree_rolls_up_sizes() {
+        let leaves = vec![
+            ("root", 0, 0),
+            ("root/a.txt", 1, 10),
+            ("root/sub", 1, 0),
+            ("root/sub/b.txt", 2, 20),
+            ("root/sub/deeper", 2, 0),
+            ("root/sub/deeper/c.txt", 3, 30),
+            ("root/d.txt", 1, 40),
+        ];
+
+        let mut stack: FileSystemStack = VecDeque::new();
+        for (key, depth, size) in leaves {
+            stack.push_back(DirTreeLeaf {
+                key: PathBuf::from(key),
+                depth,
+                size,
+                ..Default::default()
+            });
+        }
+
+        let root = build_nested_tree(&stack).unwrap();
+        assert_eq!(root.total_size, 100);
+        assert_eq!(root.file_count, 4);
+        assert_eq!(root.children.len(), 3);
+
+        let sub = &root.children[1];
+        assert!(sub.is_dir);
+        assert_eq!(sub.name(), "sub");
+        assert_eq!(sub.total_size, 50);
+        assert_eq!(sub.children[1].total_size, 30);
+        assert!(!root.children[2].is_dir);
+    }
+
+    #[test]
+    fn test_build_nested_tree_empty_stack() {
+        assert!(build_nested_tree(&VecDeque::new()).is_none());
+    }
+}
@@ -0,0 +1,96 @@
+use serde_derive::Serialize;
+
+use super::machine_output::SCHEMA_VERSION;
+use super::style::current_style;
+use crate::configs::presets::Preset;
+
+#[derive(Serialize)]
+struct PresetRecord<'a> {
+    name: &'a str,
+    description: &'a str,
+    suggested_directory: Option<&'a str>,
+    extensions_to_delete: &'a [&'a str],
+    names_to_delete: &'a [&'a str],
+    older_than_days: Option<u64>,
+    recursive: bool,
+    delete_hidden: bool,
+}
+
+#[derive(Serialize)]
+struct PresetListReport<'a> {
+    schema_version: u32,
+    presets: Vec<PresetRecord<'a>>,
+}
+
+fn preset_record(preset: &Preset) -> PresetRecord<'_> {
+    PresetRecord {
+        name: preset.name,
+        description: preset.description,
+        suggested_directory: preset.suggested_directory,
+        extensions_to_delete: preset.extensions_to_delete,
+        names_to_delete: preset.names_to_delete,
+        older_than_days: preset.older_than_days,
+        recursive: preset.recursive,
+        delete_hidden: preset.delete_hidden,
+    }
+}
+
+// What the preset matches, e.g. "deletes .swp, .bak, *~; older than 7 days".
+fn describe_rules(preset: &Preset) -> String {
+    let matches: Vec<String> = preset
+        .extensions_to_delete
+        .iter()
+        .map(|ext| format!(".{}", ext))
+        .chain(preset.names_to_delete.iter().map(|name| name.to_string()))
+        .collect();
+
+    let mut parts = vec![format!("deletes {}", matches.join(", "))];
+    if let Some(days) = preset.older_than_days {
+        parts.push(format!("older than {} days", days));
+    }
+    if preset.recursive {
+        parts.push("including subfolders".to_string());
+    }
+    if preset.delete_hidden {
+        parts.push("including hidden files".to_string());
+    }
+    parts.join("; ")
+}
+
+/// Describes each preset, along with what it deletes.
+pub fn generate_preset_list_text(presets: &[Preset]) -> String {
+    let style = current_style();
+    presets
+        .iter()
+        .map(|preset| {
+            let mut lines = vec![
+                format!("{}  {}", style.bold(preset.name), preset.description),
+                style.dimmed(&format!("  {}", describe_rules(preset))),
+            ];
+            if let Some(directory) = preset.suggested_directory {
+                lines.push(style.dimmed(&format!("  usually found in ~/{}", directory)));
+            }
+            lines.join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+pub fn generate_preset_list_json(presets: &[Preset]) -> String {
+    let report = PresetListReport {
+        schema_version: SCHEMA_VERSION,
+        presets: presets.iter().map(preset_record).collect(),
+    };
+    serde_json::to_string_pretty(&report).expect("Presets are always serialisable")
+}
+
+/// Renders one compact JSON record per preset.
+pub fn generate_preset_list_ndjson(presets: &[Preset]) -> String {
+    presets
+        .iter()
+        .map(|preset| {
+            serde_json::to_string(&preset_record(preset)).expect("Presets are always serialisable")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
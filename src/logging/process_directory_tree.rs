@@ -1,4 +1,8 @@
-use super::folder_tree_helpers::{DirTreeLeaf, DirTreeOptions};
+use super::folder_tree_helpers::{DirTreeLeaf, DirTreeOptions, TreeSortOrder};
+use super::nested_tree::{build_nested_tree, TreeNode};
+use super::style::current_style;
+use crate::utils::{format_count, format_size};
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 
 pub type FileSystemStack = VecDeque<DirTreeLeaf>;
@@ -37,41 +41,124 @@ impl DirTreeLimbs {
     }
 }
 
-fn get_single_tree_leaf(
-    leaf: DirTreeLeaf,
+// Files folded away once a directory holds more than `collapse_after` of them.
+#[derive(Default)]
+struct CollapsedFiles {
+    count: usize,
+    size: u64,
+}
+
+impl CollapsedFiles {
+    fn describe(&self) -> String {
+        let noun = match self.count {
+            1 => "file",
+            _ => "files",
+        };
+        format!(
+            "{} and {} more {} ({})",
+            current_style().symbol("…", "..."),
+            format_count(self.count),
+            noun,
+            format_size(self.size)
+        )
+    }
+}
+
+fn compare_nodes(sort: TreeSortOrder, a: &TreeNode, b: &TreeNode) -> Ordering {
+    let by_name = || a.name().cmp(&b.name());
+    match sort {
+        TreeSortOrder::Name => by_name(),
+        TreeSortOrder::Size => b.total_size.cmp(&a.total_size).then_with(by_name),
+        TreeSortOrder::Mtime => b.latest_modified.cmp(&a.latest_modified).then_with(by_name),
+    }
+}
+
+// Picks out the children to print, in order, alongside any files that
+// were collapsed into a single summary line.
+fn arrange_children<'a>(
+    children: &'a [TreeNode],
+    print_options: &DirTreeOptions,
+) -> (Vec<&'a TreeNode>, Option<CollapsedFiles>) {
+    let mut visible: Vec<&TreeNode> = children
+        .iter()
+        .filter(|child| !print_options.should_skip_leaf(child.is_dir))
+        .filter(|child| child.is_dir || !print_options.should_skip_kept_file(&child.leaf))
+        .collect();
+    visible.sort_by(|a, b| compare_nodes(print_options.sort, a, b));
+
+    let Some(collapse_after) = print_options.collapse_after else {
+        return (visible, None);
+    };
+
+    let mut collapsed = CollapsedFiles::default();
+    let mut files_shown = 0;
+    visible.retain(|child| {
+        if child.is_dir || files_shown < collapse_after {
+            files_shown += usize::from(!child.is_dir);
+            return true;
+        }
+        collapsed.count += 1;
+        collapsed.size += child.total_size;
+        false
+    });
+
+    match collapsed.count {
+        0 => (visible, None),
+        _ => (visible, Some(collapsed)),
+    }
+}
+
+fn render_tree_node(
+    node: &TreeNode,
+    is_last: bool,
     prefix_stack: &mut Vec<&str>,
-    tree_suffix: String,
-) -> String {
-    // Our prefix stack is a vector of spaces and branches.
-    // If our stack is longer than the depth, we want to remove
-    // it indicates that we've gone up a directory. See the test at
-    // bottom of the page for a visual representation of this.
-    prefix_stack.truncate(leaf.depth);
-    let (pointer, new_prefix) = DirTreeLimbs::get_pointer_and_prefix(leaf.depth, leaf.is_last);
-    let current_line = format!(
-        "{}{}{}{}",
-        prefix_stack.concat(),
-        pointer,
-        leaf,
-        tree_suffix
+    print_options: &DirTreeOptions,
+    dir_tree: &mut String,
+) {
+    // Our prefix stack is a vector of spaces and branches, one per level
+    // above the current node. See the test at the bottom of the page for a
+    // visual representation of this.
+    let depth = node.leaf.depth;
+    let (pointer, new_prefix) = DirTreeLimbs::get_pointer_and_prefix(depth, is_last);
+    let name = current_style().entry_name(
+        &node.leaf.tree_label(print_options.full_paths),
+        &node.leaf.key,
+        node.is_dir,
     );
+    let suffix = print_options.get_tree_suffix_str(&node.leaf);
+    *dir_tree += &format!("{}{}{}{}\n", prefix_stack.concat(), pointer, name, suffix);
 
-    prefix_stack.push(new_prefix);
+    if print_options.is_beyond_max_depth(depth + 1) {
+        return;
+    }
 
-    current_line + "\n" // Added newline here for consistency
+    prefix_stack.push(new_prefix);
+    let (children, collapsed) = arrange_children(&node.children, print_options);
+    for (index, child) in children.iter().enumerate() {
+        let is_last = index == children.len() - 1 && collapsed.is_none();
+        render_tree_node(child, is_last, prefix_stack, print_options, dir_tree);
+    }
+    if let Some(collapsed) = collapsed {
+        *dir_tree += &format!(
+            "{}{}{}\n",
+            prefix_stack.concat(),
+            DirTreeLimbs::LAST,
+            collapsed.describe()
+        );
+    }
+    prefix_stack.pop();
 }
 
-pub fn process_folder_tree_stack(stack: FileSystemStack, print_options: &DirTreeOptions) -> String {
+/// Renders the scanned tree, applying the depth limit, ordering and
+/// collapsing described by `print_options`.
+pub fn process_folder_tree_stack(
+    stack: &FileSystemStack,
+    print_options: &DirTreeOptions,
+) -> String {
     let mut dir_tree = String::new();
-    let mut prefix_stack: Vec<&str> = Vec::new();
 
-    for leaf in stack {
-        if print_options.should_skip_file_leaf(&leaf.key) {
-            continue;
-        }
-        let suffix = print_options.get_tree_suffix_str();
-        let leaf = get_single_tree_leaf(leaf, &mut prefix_stack, suffix);
-        dir_tree += &leaf;
+    if let Some(root) = build_nested_tree(stack) {
+        render_tree_node(&root, true, &mut Vec::new(), print_options, &mut dir_tree);
     }
 
     dir_tree
@@ -105,16 +192,18 @@ mod tests {
         ];
 
         let mut stack: FileSystemStack = VecDeque::new();
-        for (key, depth, is_last) in folder_leaves {
+        // The tree works out which entry comes last in each folder itself, so the
+        // flags only document the expected layout.
+        for (key, depth, _is_last) in folder_leaves {
             stack.push_back(DirTreeLeaf {
                 key: PathBuf::from(key),
                 depth,
-                is_last,
+                ..Default::default()
             });
         }
 
         let options = DirTreeOptions::default();
-        let directory_tree = process_folder_tree_stack(stack, &options); // Ensure this function returns a String
+        let directory_tree = process_folder_tree_stack(&stack, &options); // Ensure this function returns a String
 
         let expected_output = "\
 .main_folder
@@ -138,4 +227,59 @@ mod tests {
 
         assert_eq!(directory_tree, expected_output);
     }
+
+    fn build_sized_stack() -> FileSystemStack {
+        let leaves = vec![
+            ("root", 0, true, 0),
+            ("a.txt", 1, false, 10),
+            ("b.txt", 1, false, 30),
+            ("c.txt", 1, false, 20),
+            ("d.txt", 1, false, 5),
+            ("sub", 1, true, 0),
+            ("e.txt", 2, false, 1),
+        ];
+
+        leaves
+            .into_iter()
+            .map(|(key, depth, is_dir, size)| DirTreeLeaf {
+                key: PathBuf::from(key),
+                depth,
+                is_dir,
+                size,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_process_folder_tree_stack_sorts_and_collapses() {
+        let options = DirTreeOptions {
+            sort: TreeSortOrder::Size,
+            collapse_after: Some(2),
+            ..Default::default()
+        };
+        let directory_tree = process_folder_tree_stack(&build_sized_stack(), &options);
+
+        let expected_output = "\
+.root
+├── b.txt
+├── c.txt
+├── sub
+│   └── e.txt
+└── … and 2 more files (15.00 B)
+";
+        assert_eq!(directory_tree, expected_output);
+    }
+
+    #[test]
+    fn test_process_folder_tree_stack_limits_depth_and_files() {
+        let options = DirTreeOptions {
+            display_files: false,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let directory_tree = process_folder_tree_stack(&build_sized_stack(), &options);
+
+        assert_eq!(directory_tree, ".root\n└── sub\n");
+    }
 }
@@ -0,0 +1,220 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::env;
+use std::io::{stdout, IsTerminal};
+use std::path::Path;
+use std::sync::OnceLock;
+
+// The style used for the rest of the run, set up once the CLI has been parsed.
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+// Keep test output free of escape codes, whatever terminal the tests run in.
+const DEFAULT_COLOR_CHOICE: ColorChoice = match cfg!(test) {
+    true => ColorChoice::Never,
+    false => ColorChoice::Auto,
+};
+
+/// When to colour the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colour the output if it's going to a terminal and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Colours taken from the `LS_COLORS` environment variable, used to colour tree
+/// entries the same way `ls` does. Only directories, regular files and `*.ext`
+/// patterns are supported.
+#[derive(Debug, Default)]
+pub struct LsColors {
+    directory: Option<String>,
+    file: Option<String>,
+    extensions: HashMap<String, String>,
+}
+
+impl LsColors {
+    pub fn parse(ls_colors: &str) -> LsColors {
+        let mut colours = LsColors::default();
+
+        for (pattern, code) in ls_colors
+            .split(':')
+            .filter_map(|entry| entry.split_once('='))
+        {
+            match pattern {
+                "di" => colours.directory = Some(code.to_string()),
+                "fi" => colours.file = Some(code.to_string()),
+                _ => {
+                    if let Some(ext) = pattern.strip_prefix("*.") {
+                        colours
+                            .extensions
+                            .insert(ext.to_lowercase(), code.to_string());
+                    }
+                }
+            }
+        }
+        colours
+    }
+
+    fn code_for(&self, path: &Path, is_dir: bool) -> Option<&str> {
+        if is_dir {
+            return self.directory.as_deref();
+        }
+        path.extension()
+            .and_then(|ext| self.extensions.get(&ext.to_string_lossy().to_lowercase()))
+            .or(self.file.as_ref())
+            .map(|code| code.as_str())
+    }
+}
+
+/// Decides how text is decorated: whether ANSI colours are used, whether emoji
+/// and symbols are shown, and how tree entries are coloured.
+#[derive(Debug)]
+pub struct Style {
+    colour: bool,
+    emoji: bool,
+    ls_colors: LsColors,
+}
+
+impl Style {
+    pub fn new(colour: bool, emoji: bool, ls_colors: LsColors) -> Style {
+        Style {
+            colour,
+            emoji,
+            ls_colors,
+        }
+    }
+
+    fn detect(choice: ColorChoice, plain: bool) -> Style {
+        let colour = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                // See https://no-color.org - any non-empty value disables colour.
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                let dumb_terminal = env::var("TERM").is_ok_and(|term| term == "dumb");
+                stdout().is_terminal() && !no_color && !dumb_terminal
+            }
+        };
+        let ls_colors = env::var("LS_COLORS")
+            .map(|value| LsColors::parse(&value))
+            .unwrap_or_default();
+
+        Style::new(colour, !plain, ls_colors)
+    }
+
+    pub fn uses_colour(&self) -> bool {
+        self.colour
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        match self.colour {
+            true => format!("\x1b[{}m{}\x1b[0m", code, text),
+            false => text.to_string(),
+        }
+    }
+
+    pub fn bold(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    pub fn red(&self, text: &str) -> String {
+        self.paint("31", text)
+    }
+
+    pub fn dimmed(&self, text: &str) -> String {
+        self.paint("2", text)
+    }
+
+    /// Wraps a heading in emoji, e.g. "📁 Cleaning Overview 📁".
+    pub fn heading(&self, emoji: &str, text: &str) -> String {
+        match self.emoji {
+            true => format!("{} {} {}", emoji, text, emoji),
+            false => text.to_string(),
+        }
+    }
+
+    /// Puts an emoji in front of the text, e.g. "👉 Would you like to...".
+    pub fn prefixed(&self, emoji: &str, text: &str) -> String {
+        match self.emoji {
+            true => format!("{} {}", emoji, text),
+            false => text.to_string(),
+        }
+    }
+
+    /// Picks between a decorative symbol and its plain text fallback.
+    pub fn symbol<'a>(&self, fancy: &'a str, plain: &'a str) -> &'a str {
+        match self.emoji {
+            true => fancy,
+            false => plain,
+        }
+    }
+
+    /// Colours the name of a tree entry according to `LS_COLORS`.
+    pub fn entry_name(&self, name: &str, path: &Path, is_dir: bool) -> String {
+        match self.ls_colors.code_for(path, is_dir) {
+            Some(code) => self.paint(code, name),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Sets up the style for the rest of the run. Only the first call has any effect.
+pub fn init_style(choice: ColorChoice, plain: bool) {
+    STYLE.get_or_init(|| Style::detect(choice, plain));
+}
+
+/// The style set up by `init_style`, or the automatically detected one if it was never called.
+pub fn current_style() -> &'static Style {
+    STYLE.get_or_init(|| Style::detect(DEFAULT_COLOR_CHOICE, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colours_only_applied_when_enabled() {
+        let coloured = Style::new(true, true, LsColors::default());
+        let plain = Style::new(false, false, LsColors::default());
+
+        assert_eq!(coloured.bold("size"), "\x1b[1msize\x1b[0m");
+        assert_eq!(coloured.red("deleted"), "\x1b[31mdeleted\x1b[0m");
+        assert_eq!(plain.bold("size"), "size");
+        assert_eq!(plain.dimmed("kept"), "kept");
+    }
+
+    #[test]
+    fn test_plain_style_drops_emoji() {
+        let fancy = Style::new(false, true, LsColors::default());
+        let plain = Style::new(false, false, LsColors::default());
+
+        assert_eq!(fancy.heading("📁", "Overview"), "📁 Overview 📁");
+        assert_eq!(plain.heading("📁", "Overview"), "Overview");
+        assert_eq!(plain.prefixed("👉", "Continue?"), "Continue?");
+        assert_eq!(plain.symbol("…", "..."), "...");
+    }
+
+    #[test]
+    fn test_entry_names_coloured_by_ls_colors() {
+        let ls_colors = LsColors::parse("rs=0:di=01;34:fi=0:*.LOG=00;31:ln=01;36");
+        let style = Style::new(true, true, ls_colors);
+
+        assert_eq!(
+            style.entry_name("src", Path::new("src"), true),
+            "\x1b[01;34msrc\x1b[0m"
+        );
+        assert_eq!(
+            style.entry_name("app.log", Path::new("app.log"), false),
+            "\x1b[00;31mapp.log\x1b[0m"
+        );
+        assert_eq!(
+            style.entry_name("main.rs", Path::new("main.rs"), false),
+            "\x1b[0mmain.rs\x1b[0m"
+        );
+
+        let uncoloured = Style::new(false, true, LsColors::parse("di=01;34"));
+        assert_eq!(uncoloured.entry_name("src", Path::new("src"), true), "src");
+    }
+}
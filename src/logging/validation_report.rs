@@ -0,0 +1,48 @@
+use super::style::current_style;
+use crate::configs::validation::{Diagnostic, Severity, ValidationReport};
+
+fn format_diagnostic(diagnostic: &Diagnostic) -> String {
+    let style = current_style();
+    let label = match diagnostic.severity {
+        Severity::Error => style.red(&style.bold("error")),
+        Severity::Warning => style.bold("warning"),
+    };
+    let message = match &diagnostic.location {
+        Some(location) => format!("[{}] {}", location, diagnostic.message),
+        None => diagnostic.message.clone(),
+    };
+
+    let mut lines = vec![format!("  {}: {}", label, message)];
+    if let Some(snippet) = &diagnostic.snippet {
+        lines.extend(snippet.lines().map(|line| format!("    {}", line)));
+    }
+    if let Some(hint) = &diagnostic.hint {
+        lines.push(style.dimmed(&format!("    hint: {}", hint)));
+    }
+    lines.join("\n")
+}
+
+fn pluralise(count: usize, word: &str) -> String {
+    match count {
+        1 => format!("1 {}", word),
+        _ => format!("{} {}s", count, word),
+    }
+}
+
+/// Lists the problems found in a config file, followed by a count of each kind.
+pub fn generate_validation_text(report: &ValidationReport) -> String {
+    let style = current_style();
+    let mut lines = vec![style.bold(&format!("Checking {:?}", report.path))];
+    lines.extend(report.diagnostics.iter().map(format_diagnostic));
+
+    let summary = match report.diagnostics.is_empty() {
+        true => style.prefixed("✅", "No problems found."),
+        false => format!(
+            "{}, {}.",
+            pluralise(report.count(Severity::Error), "error"),
+            pluralise(report.count(Severity::Warning), "warning")
+        ),
+    };
+    lines.push(format!("  {}", summary));
+    lines.join("\n")
+}
@@ -1,3 +1,4 @@
+mod browser;
 mod cleaning;
 mod cli_tools;
 mod configs;
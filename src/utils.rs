@@ -1,5 +1,5 @@
 use core::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn check_root_folder_exists(root_folder: &str) {
     let root = PathBuf::from(root_folder);
@@ -10,7 +10,7 @@ pub fn check_root_folder_exists(root_folder: &str) {
     }
 }
 
-pub fn is_hidden_file(path: &PathBuf) -> bool {
+pub fn is_hidden_file(path: &Path) -> bool {
     path.file_name()
         .and_then(|n| n.to_str())
         .map(|s| s.starts_with('.'))
@@ -31,10 +31,58 @@ pub fn format_size(bytes: u64) -> String {
     format!("{:.2} {}", bytes, units.last().unwrap())
 }
 
+/// Formats a count with thousands separators, e.g. `4812` becomes `4,812`.
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Converts a path into a `String`, escaping backslashes as `\\` and any bytes that
+/// aren't valid UTF-8 as `\xNN`.
+///
+/// On Unix nothing is thrown away, unlike with `to_string_lossy`, so two distinct
+/// paths never end up with the same text. Elsewhere the path is converted lossily.
+pub fn escape_path(path: &Path) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut escaped = String::new();
+        for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+            escaped.push_str(&chunk.valid().replace('\\', "\\\\"));
+            for byte in chunk.invalid() {
+                escaped.push_str(&format!("\\x{:02X}", byte));
+            }
+        }
+        escaped
+    }
+
+    #[cfg(not(unix))]
+    {
+        path.to_string_lossy().into_owned()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(812), "812");
+        assert_eq!(format_count(4812), "4,812");
+        assert_eq!(format_count(1234567), "1,234,567");
+    }
+
     #[test]
     fn test_format_size_various() {
         let test_cases = vec![
@@ -55,4 +103,20 @@ mod tests {
             assert_eq!(format_size(bytes), expected, "Failed at {} bytes", bytes);
         }
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_escape_path_invalid_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/tmp/caf\xE9.txt"));
+        assert_eq!(escape_path(path), "/tmp/caf\\xE9.txt");
+        assert_eq!(escape_path(Path::new("/tmp/plain.txt")), "/tmp/plain.txt");
+        // A literal `\xE9` in a valid name must not look like the escaped byte
+        assert_eq!(
+            escape_path(Path::new("/tmp/caf\\xE9.txt")),
+            "/tmp/caf\\\\xE9.txt"
+        );
+    }
 }